@@ -1,4 +1,7 @@
-use crate::{Card, CardRankSlint, CardSlint, CardSuitSlint, Player, PlayerSlint, Rank, Suit};
+use crate::{
+    Card, CardRankSlint, CardSlint, CardSuitSlint, Player, PlayerSlint, Rank, RoundTrigger,
+    RulesSlint, ScoringMode, Suit, TableInfo, TableRules, TableSlint,
+};
 
 impl From<Card> for CardSlint {
     fn from(card: Card) -> Self {
@@ -43,3 +46,72 @@ impl From<Player> for PlayerSlint {
         }
     }
 }
+
+impl From<TableInfo> for TableSlint {
+    fn from(table: TableInfo) -> Self {
+        TableSlint {
+            id: table.id as i32,
+            name: table
+                .name
+                .unwrap_or_else(|| format!("Table {}", table.id))
+                .into(),
+            players: table.players.join(", ").into(),
+            seats: format!("{}/{}", table.players.len(), table.table_size).into(),
+            rules: rules_text(&table.rules).into(),
+        }
+    }
+}
+
+/// The house rules of a table in a few words, as shown in the table list.
+fn rules_text(rules: &TableRules) -> String {
+    let mut text = [
+        (rules.ramsch, "Ramsch"),
+        (rules.schieberamsch, "Schieberamsch"),
+        (rules.kontra, "Kontra/Re"),
+        (!rules.bock_triggers.is_empty(), "Bock"),
+        (!rules.ramsch_triggers.is_empty(), "Ramsch rounds"),
+        (rules.null_matadors, "Null matadors"),
+        (rules.scoring == ScoringMode::SeegerFabian, "Seeger-Fabian"),
+    ]
+    .into_iter()
+    .filter(|(played, _)| *played)
+    .map(|(_, rule)| rule)
+    .collect::<Vec<_>>();
+
+    if text.is_empty() {
+        text.push("no house rules");
+    }
+    text.join(", ")
+}
+
+impl From<RulesSlint> for TableRules {
+    fn from(rules: RulesSlint) -> Self {
+        // Bock and Ramsch rounds start after every event that is commonly played as a trigger
+        let triggers = |played: bool| {
+            if played {
+                vec![
+                    RoundTrigger::LostKontra,
+                    RoundTrigger::SixtySixty,
+                    RoundTrigger::HighValue,
+                ]
+            } else {
+                Vec::new()
+            }
+        };
+        let scoring = if rules.seeger_fabian {
+            ScoringMode::SeegerFabian
+        } else {
+            ScoringMode::GameValues
+        };
+
+        TableRules {
+            ramsch: rules.ramsch,
+            schieberamsch: rules.schieberamsch,
+            kontra: rules.kontra,
+            bock_triggers: triggers(rules.bock),
+            ramsch_triggers: triggers(rules.ramsch_rounds),
+            null_matadors: rules.null_matadors,
+            scoring,
+        }
+    }
+}
@@ -19,6 +19,8 @@ struct AppModel {
     pub state: AppState,
     pub other_player: Vec<Player>,
     name: Option<String>,
+    /// issued with `ConfirmJoin`, takes the seat back after the connection dropped
+    pub resume_token: Option<String>,
 }
 
 impl AppModel {
@@ -28,9 +30,51 @@ impl AppModel {
             state: AppState::Login,
             other_player: Vec::new(),
             name: None,
+            resume_token: None,
         }
     }
 
+    fn player_name(&self, id: u32) -> String {
+        if id == self.player_id {
+            return self.name.clone().unwrap_or_default();
+        }
+        self.other_player
+            .iter()
+            .find(|p| p.id == id)
+            .map_or_else(|| format!("player {}", id), |p| p.name.clone())
+    }
+
+    /// One line per player with their score.
+    fn scores_text(&self, ids: &[u32], scores: &[i32]) -> String {
+        ids.iter()
+            .zip(scores)
+            .map(|(&id, score)| format!("{}: {}", self.player_name(id), score))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn standings_text(&self, standings: &StandingsMessage) -> String {
+        format!(
+            "Hand {}/{}\n{}",
+            standings.entry.hand,
+            standings.hands,
+            self.scores_text(&standings.ids, &standings.scores)
+        )
+    }
+
+    /// What a new connection has to send to pick up where the last one stopped.
+    fn reconnect_messages(&self) -> Vec<Message> {
+        let mut msgs = Vec::new();
+        if let (AppState::Game, Some(resume_token)) = (self.state, &self.resume_token) {
+            msgs.push(Message::Resume(resume_token.clone()));
+        }
+        if let Some(name) = &self.name {
+            msgs.push(Message::Login(name.clone()));
+            msgs.push(Message::ListTables);
+        }
+        msgs
+    }
+
     fn submit_name(&mut self, name: String) {
         if !name.trim().is_empty() {
             self.state = AppState::Lobby;
@@ -80,16 +124,17 @@ async fn main() -> Result<(), slint::PlatformError> {
         let sock_tx = sock_tx.clone();
 
         move |name| {
-            if name == "" {
+            if name.is_empty() {
                 return;
             }
             let mut app_model = app_model.lock().unwrap();
             app_model.submit_name(name.to_string());
             if let Some(ui) = ui_weak.upgrade() {
-                ui.set_name(name.clone().into());
+                ui.set_name(name.clone());
                 ui.set_app_state(AppState::Lobby);
 
                 let _ = sock_tx.send(Message::Login(name.into()));
+                let _ = sock_tx.send(Message::ListTables);
             }
         }
     });
@@ -101,8 +146,57 @@ async fn main() -> Result<(), slint::PlatformError> {
         }
     });
 
-    ui.on_add_npc(move || {
-        let _ = sock_tx.send(Message::AddNPC);
+    ui.on_join_table({
+        let sock_tx = sock_tx.clone();
+        move |id| {
+            let _ = sock_tx.send(Message::JoinTable(id as u32));
+        }
+    });
+
+    ui.on_create_table({
+        let sock_tx = sock_tx.clone();
+        move |name, private, rules| {
+            let name = (!name.trim().is_empty()).then(|| name.trim().to_string());
+            let _ = sock_tx.send(Message::CreateTable(CreateTableMessage {
+                name,
+                private,
+                rules: rules.into(),
+            }));
+        }
+    });
+
+    ui.on_join_private_table({
+        let sock_tx = sock_tx.clone();
+        move |invite_code| {
+            if invite_code.trim().is_empty() {
+                return;
+            }
+            let _ = sock_tx.send(Message::JoinPrivateTable(invite_code.trim().to_string()));
+        }
+    });
+
+    ui.on_add_npc({
+        let sock_tx = sock_tx.clone();
+        move |difficulty, name| {
+            let difficulty = match difficulty {
+                0 => NpcDifficulty::RandomLegal,
+                2 => NpcDifficulty::Search,
+                _ => NpcDifficulty::Heuristic,
+            };
+            let name = (!name.trim().is_empty()).then(|| name.trim().to_string());
+            let _ = sock_tx.send(Message::AddNPC(AddNpcMessage { difficulty, name }));
+        }
+    });
+
+    ui.on_set_table_size({
+        let sock_tx = sock_tx.clone();
+        move |seats| {
+            let _ = sock_tx.send(Message::TableSize(seats as u32));
+        }
+    });
+
+    ui.on_set_session_length(move |hands| {
+        let _ = sock_tx.send(Message::SessionLength(hands as u32));
     });
 
     let weak_app = ui.as_weak();
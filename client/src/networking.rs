@@ -1,11 +1,11 @@
-use crate::{AppState, CardSlint, MainWindow, Player, PlayerSlint};
+use crate::{AppState, CardSlint, MainWindow, Player, PlayerSlint, TableSlint};
 use proto::*;
-use slint::{Model, VecModel, Weak};
-use std::sync::mpsc;
+use slint::{Model, ModelRc, VecModel, Weak};
 use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::TcpStream;
+use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 
 const IP_ADDR: &str = "127.0.0.1:6969";
@@ -13,9 +13,9 @@ const IP_ADDR: &str = "127.0.0.1:6969";
 pub fn connect_to_server(
     app_model: Arc<Mutex<crate::AppModel>>,
     ui: Weak<MainWindow>,
-) -> mpsc::Sender<Message> {
-    let (sock_tx, sock_rx) = mpsc::channel::<Message>();
-    let sock_rx = Arc::new(Mutex::new(sock_rx));
+) -> mpsc::UnboundedSender<Message> {
+    let (sock_tx, sock_rx) = mpsc::unbounded_channel::<Message>();
+    let sock_rx = Arc::new(tokio::sync::Mutex::new(sock_rx));
 
     let msg_sender = sock_tx.clone();
     tokio::spawn(async move {
@@ -30,12 +30,20 @@ pub fn connect_to_server(
                     let (reader, writer) = tokio::net::TcpStream::into_split(tcp_stream);
                     let reader = BufReader::new(reader);
 
+                    // whatever was queued for the lost connection is out of date
+                    while msg_channel.lock().await.try_recv().is_ok() {}
+                    for msg in app_model.lock().unwrap().reconnect_messages() {
+                        let _ = msg_sender.send(msg);
+                    }
+
                     let keep_alive_tread = spawn_keep_alive_thread(msg_sender);
                     let sender_thread = spawn_sender_thread(msg_channel, writer);
                     let reciever_thread = spawn_reciever_thread(app_model, ui, reader);
-                    keep_alive_tread.await.unwrap();
-                    sender_thread.await.unwrap();
-                    reciever_thread.await.unwrap();
+                    // the server closing the connection ends the receiver, the others would go
+                    // on forever
+                    let _ = reciever_thread.await;
+                    keep_alive_tread.abort();
+                    sender_thread.abort();
                     println!("connection to server lost");
                 }
                 Err(_) => println!("could not connect to server! retry in 1 sec"),
@@ -46,38 +54,29 @@ pub fn connect_to_server(
     sock_tx
 }
 
-fn spawn_keep_alive_thread(sender: mpsc::Sender<Message>) -> tokio::task::JoinHandle<()> {
+fn spawn_keep_alive_thread(sender: mpsc::UnboundedSender<Message>) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         loop {
-            let sender = sender.clone();
-            tokio::task::spawn_blocking(move || {
-                let _ = sender.send(Message::KeepAlive(system_time()));
-            })
-            .await
-            .unwrap();
+            let _ = sender.send(Message::KeepAlive(system_time()));
             sleep(Duration::from_millis(1000)).await;
         }
     })
 }
 
 fn spawn_sender_thread(
-    msg_channel: Arc<Mutex<mpsc::Receiver<Message>>>,
+    msg_channel: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<Message>>>,
     mut writer: OwnedWriteHalf,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         loop {
-            let msg_channel = Arc::clone(&msg_channel);
-            let msg = tokio::task::spawn_blocking(move || msg_channel.lock().unwrap().recv())
-                .await
-                .unwrap();
+            let msg = msg_channel.lock().await.recv().await;
 
-            if let Ok(msg) = msg {
+            if let Some(msg) = msg {
                 println!("sending Message: {:?}", msg);
                 let mut msg = serde_json::to_string(&msg).unwrap();
                 msg.push('\n');
-                match writer.write_all(&msg.as_bytes()).await {
-                    Err(_) => break,
-                    _ => {}
+                if writer.write_all(msg.as_bytes()).await.is_err() {
+                    break;
                 }
             }
             sleep(Duration::from_millis(1)).await;
@@ -96,17 +95,56 @@ fn spawn_reciever_thread(
 
             let mut buf = String::new();
             match socket.read_line(&mut buf).await {
-                Err(_) => break,
-                _ => {}
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let msg: Message = match serde_json::from_str(&buf) {
+                Ok(msg) => msg,
+                Err(e) => {
+                    println!("could not read a message from the server: {}", e);
+                    break;
+                }
             };
-            let msg: Message = serde_json::from_str(&buf)
-                .unwrap_or_else(|e| panic!("unreachable deserialize should always work: {}", e));
 
             println!("recieved Message: {:?}", msg);
 
             match msg {
-                Message::ConfirmJoin(id) => {
-                    app_model.lock().unwrap().player_id = id;
+                Message::ConfirmJoin(confirm_join) => {
+                    let mut app_model = app_model.lock().unwrap();
+                    app_model.player_id = confirm_join.id;
+                    app_model.resume_token = Some(confirm_join.resume_token);
+                }
+                Message::RoundModifier(modifier) => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            let hand_model = ui.get_hand();
+                            let vec_model = hand_model
+                                .as_any()
+                                .downcast_ref::<VecModel<CardSlint>>()
+                                .unwrap();
+
+                            vec_model.clear();
+                            let round = match modifier {
+                                RoundModifier::Normal => "",
+                                RoundModifier::Bock => "Bock",
+                                RoundModifier::Ramsch => "Ramsch",
+                            };
+                            ui.set_game_type(round.into());
+                        }
+                    });
+                }
+                Message::Redeal => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            let hand_model = ui.get_hand();
+                            let vec_model = hand_model
+                                .as_any()
+                                .downcast_ref::<VecModel<CardSlint>>()
+                                .unwrap();
+
+                            vec_model.clear();
+                        }
+                    });
                 }
                 Message::DrawCard(card) => {
                     let _ = slint::invoke_from_event_loop(move || {
@@ -121,6 +159,76 @@ fn spawn_reciever_thread(
                         }
                     });
                 }
+                Message::Skat(skat) => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            let hand_model = ui.get_hand();
+                            let vec_model = hand_model
+                                .as_any()
+                                .downcast_ref::<VecModel<CardSlint>>()
+                                .unwrap();
+
+                            for card in skat {
+                                vec_model.push(card.into());
+                            }
+                        }
+                    });
+                }
+                Message::Discard(cards) => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            let hand_model = ui.get_hand();
+                            let vec_model = hand_model
+                                .as_any()
+                                .downcast_ref::<VecModel<CardSlint>>()
+                                .unwrap();
+
+                            for card in cards {
+                                let card = CardSlint::from(card);
+                                if let Some(index) = vec_model.iter().position(|c| c == card) {
+                                    vec_model.remove(index);
+                                }
+                            }
+                        }
+                    });
+                }
+                Message::GameSnapshot(snapshot) => {
+                    let standings = {
+                        let mut app_model = app_model.lock().unwrap();
+                        app_model.state = AppState::Game;
+                        snapshot
+                            .standings
+                            .as_ref()
+                            .map(|standings| app_model.standings_text(standings))
+                            .unwrap_or_default()
+                    };
+                    let game_type = match (snapshot.declaration, snapshot.modifier) {
+                        (Some(declaration), _) => declaration.to_string(),
+                        (None, RoundModifier::Normal) => String::new(),
+                        (None, RoundModifier::Bock) => "Bock".into(),
+                        (None, RoundModifier::Ramsch) => "Ramsch".into(),
+                    };
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            let hand_model = ui.get_hand();
+                            let vec_model = hand_model
+                                .as_any()
+                                .downcast_ref::<VecModel<CardSlint>>()
+                                .unwrap();
+
+                            vec_model.set_vec(
+                                snapshot
+                                    .hand
+                                    .into_iter()
+                                    .map(CardSlint::from)
+                                    .collect::<Vec<_>>(),
+                            );
+                            ui.set_game_type(game_type.into());
+                            ui.set_standings(standings.into());
+                            ui.set_app_state(AppState::Game);
+                        }
+                    });
+                }
                 Message::PlayerJoin(new_player) => {
                     let mut app_model = app_model.lock().unwrap();
 
@@ -131,7 +239,9 @@ fn spawn_reciever_thread(
                         app_model.state = AppState::PendingGame;
                         let ui = ui.clone();
                         let _ = slint::invoke_from_event_loop(move || {
-                            ui.unwrap().set_app_state(AppState::PendingGame);
+                            let ui = ui.unwrap();
+                            ui.set_join_rejected("".into());
+                            ui.set_app_state(AppState::PendingGame);
                         });
                     }
 
@@ -176,10 +286,102 @@ fn spawn_reciever_thread(
                         }
                     });
                 }
+                Message::Trump(declaration) => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            ui.set_game_type(declaration.to_string().into());
+                        }
+                    });
+                }
+                Message::TableList(tables) => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            let tables =
+                                tables.into_iter().map(TableSlint::from).collect::<Vec<_>>();
+                            ui.set_tables(ModelRc::new(VecModel::from(tables)));
+                        }
+                    });
+                }
+                Message::InviteCode(invite_code) => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            ui.set_invite_code(invite_code.into());
+                        }
+                    });
+                }
+                Message::JoinRejected(reason) => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            ui.set_join_rejected(reason.into());
+                        }
+                    });
+                }
+                Message::TableSize(seats) => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            ui.set_table_size(seats as i32);
+                        }
+                    });
+                }
+                Message::SittingOut(id) if app_model.lock().unwrap().player_id == id => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            ui.set_game_type("You deal and sit out this hand".into());
+                        }
+                    });
+                }
+                Message::SessionLength(hands) => {
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            ui.set_session_length(hands as i32);
+                        }
+                    });
+                }
+                Message::Standings(standings) => {
+                    let text = app_model.lock().unwrap().standings_text(&standings);
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            ui.set_standings(text.into());
+                        }
+                    });
+                }
+                Message::SessionSummary(summary) => {
+                    let scores = app_model
+                        .lock()
+                        .unwrap()
+                        .scores_text(&summary.skat_list.ids, &summary.scores);
+                    let scoring = match summary.scoring {
+                        ScoringMode::GameValues => "game values",
+                        ScoringMode::SeegerFabian => "Seeger-Fabian",
+                    };
+                    let text = format!(
+                        "Session over after {} hands ({})\n{}",
+                        summary.skat_list.entries.len(),
+                        scoring,
+                        scores
+                    );
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            ui.set_summary(text.into());
+                        }
+                    });
+                }
+                Message::BackToLobby => {
+                    app_model.lock().unwrap().state = AppState::Lobby;
+                    let _ = slint::invoke_from_event_loop(move || {
+                        if let Some(ui) = ui.upgrade() {
+                            ui.set_standings("".into());
+                            ui.set_invite_code("".into());
+                            ui.set_app_state(AppState::Lobby);
+                        }
+                    });
+                }
                 Message::StartGame => {
                     app_model.lock().unwrap().state = AppState::Game;
                     let _ = slint::invoke_from_event_loop(move || {
-                        ui.unwrap().set_app_state(AppState::Game);
+                        let ui = ui.unwrap();
+                        ui.set_summary("".into());
+                        ui.set_app_state(AppState::Game);
                     });
                 }
                 _ => {}
@@ -0,0 +1,39 @@
+/// Sent as a `Message::Bid` to pass instead of saying or holding a value.
+pub const PASS: u32 = 0;
+
+/// Every value that can be said while bidding (Reizen), in ascending order.
+pub const BID_LADDER: [u32; 63] = [
+    18, 20, 22, 23, 24, 27, 30, 33, 35, 36, 40, 44, 45, 46, 48, 50, 54, 55, 59, 60, 63, 66, 70, 72,
+    77, 80, 81, 84, 88, 90, 96, 99, 100, 108, 110, 117, 120, 121, 126, 130, 132, 135, 140, 143,
+    144, 150, 153, 154, 156, 160, 162, 165, 168, 170, 176, 180, 187, 192, 198, 204, 216, 240, 264,
+];
+
+pub fn is_valid_bid(value: u32) -> bool {
+    BID_LADDER.contains(&value)
+}
+
+/// The lowest value that may be said after `current` (`PASS` if nothing was said yet).
+pub fn next_bid(current: u32) -> Option<u32> {
+    BID_LADDER.iter().copied().find(|&v| v > current)
+}
+
+/// The seat a player has during bidding, relative to the dealer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BidRole {
+    /// Vorhand: hears first and leads the first trick
+    Forehand,
+    /// Mittelhand: says to the forehand
+    Middlehand,
+    /// Hinterhand: says further to whoever is left
+    Rearhand,
+}
+
+impl BidRole {
+    pub fn of_seat(seat: usize, forehand: usize) -> BidRole {
+        match (seat + 3 - forehand) % 3 {
+            0 => BidRole::Forehand,
+            1 => BidRole::Middlehand,
+            _ => BidRole::Rearhand,
+        }
+    }
+}
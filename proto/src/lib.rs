@@ -1,38 +1,217 @@
 use serde::{Deserialize, Serialize};
 
+mod bidding;
+mod rounds;
+mod rules;
+mod scoring;
+mod skat_list;
+mod snapshot;
+mod table_rules;
+#[cfg(test)]
+mod tests;
+
+pub use bidding::*;
+pub use rounds::*;
+pub use rules::*;
+pub use scoring::*;
+pub use skat_list::*;
+pub use snapshot::*;
+pub use table_rules::*;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum Message {
     #[default]
     None,
     Login(String),
-    ConfirmJoin(u32),
+    ConfirmJoin(ConfirmJoinMessage),
+    /// takes back the seat of a dropped connection, with the token from its `ConfirmJoin`
+    Resume(String),
     PlayerJoin(PlayerJoinMessage),
+    /// sent before every deal
+    RoundModifier(RoundModifier),
+    /// everybody passed and Ramsch isn't played, the cards are collected and dealt again
+    Redeal,
+    /// id of the dealer, who gets no cards and watches the hand at a four-player table
+    SittingOut(u32),
     DrawCard(Card),
     Hear,
     Say,
     SayFurther,
-    Bid(i32),
-    NewBid(i32),
+    /// a value from the `BID_LADDER`, or `PASS`
+    Bid(u32),
+    BidTurn(BidTurnMessage),
+    NewBid(NewBidMessage),
+    /// id of the player that passed
+    Passed(u32),
     PlayCard(Card),
+    /// the played card is not in the hand or does not follow suit, the player has to play another one
+    IllegalCard(Card),
+    CardPlayed(CardPlayedMessage),
     PlayNormalSolo,
     PlayNormalDuo,
+    /// everybody passed, so everybody plays for themselves
+    PlayRamsch,
+    /// the player may pick up the Skat with `TakeSkat(true)`, or leave it with `TakeSkat(false)`
+    /// to play Hand
+    SkatTurn,
+    TakeSkat(bool),
+    /// the two Skat cards, sent to the player that picked them up
+    Skat(Vec<Card>),
+    /// the two cards put back into the Skat, sent back to the player once they are accepted
+    Discard(Vec<Card>),
+    /// the cards that ended up in the Skat, shown to everyone at the end of the hand
+    SkatRevealed(Vec<Card>),
+    /// id of the player that pushed the Skat on unseen in Schieberamsch
+    SkatPushed(u32),
+    RamschResult(RamschResultMessage),
     YourTurn,
-    Trump(Suit),
+    /// the game the declarer plays, broadcast to everyone once it is declared
+    Trump(Declaration),
+    /// a defender may double the game with `Kontra(true)` before the first card
+    KontraTurn,
+    Kontra(bool),
+    /// the declarer may answer a Kontra with `Re(true)`
+    ReTurn,
+    Re(bool),
+    /// id of the defender that announced Kontra
+    KontraAnnounced(u32),
+    /// id of the declarer that announced Re
+    ReAnnounced(u32),
+    /// the declarer's cards in Ouvert games
+    OpenHand(OpenHandMessage),
     GameWon(GameWonMessage),
+    /// the running scores, sent after every hand of a session
+    Standings(StandingsMessage),
+    /// the whole Skat list and the final scores, sent once the last hand of a session is played
+    SessionSummary(SessionSummaryMessage),
+    /// everything the seat knows about the running game, so a client can rebuild its state
+    GameSnapshot(Box<GameSnapshot>),
     KeepAlive(u128),
     BackToLobby,
+    /// joins the first open table with a free seat, or opens a new one
     JoinGame,
+    /// asks for the open tables, answered with a `TableList`
+    ListTables,
+    /// the open tables, also sent to everybody in the lobby whenever they change
+    TableList(Vec<TableInfo>),
+    /// opens a new table and joins it
+    CreateTable(CreateTableMessage),
+    /// joins the open table with the given id
+    JoinTable(u32),
+    /// joins the private table with the given invite code
+    JoinPrivateTable(String),
+    /// the code others need to join the private table, sent to everybody joining it
+    InviteCode(String),
+    /// the table could not be joined, with the reason why
+    JoinRejected(String),
     PlayerLeave(u32),
-    AddNPC,
+    /// adds a bot to the pending game
+    AddNPC(AddNpcMessage),
+    /// how many players the pending game waits for, three or four, a player proposes it and the
+    /// server tells everybody the agreed value
+    TableSize(u32),
+    /// how many hands the session of the pending game lasts, a player proposes it and the server
+    /// tells everybody the agreed value
+    SessionLength(u32),
     StartGame,
+    Error(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfirmJoinMessage {
+    pub id: u32,
+    /// lets a reconnecting client take its seat back with `Resume`
+    pub resume_token: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GameWonMessage {
-    pub id: Option<u32>,
-    pub winner_points: u32,
-    pub loser_points: u32,
+    /// id of the declarer (solo player)
+    pub id: u32,
+    pub result: GameResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct CreateTableMessage {
+    pub name: Option<String>,
+    /// private tables are left out of the table list and only joinable with their invite code
+    pub private: bool,
+    pub rules: TableRules,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct AddNpcMessage {
+    pub difficulty: NpcDifficulty,
+    /// the server picks a name if there is none, and makes it distinct at the table
+    pub name: Option<String>,
+}
+
+/// How a bot added to a table chooses its cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NpcDifficulty {
+    /// never bids and plays any legal card
+    RandomLegal,
+    /// judges its hand and plays by simple rules
+    #[default]
+    Heuristic,
+    /// searches sampled deals of the unseen cards for the best card
+    Search,
+}
+
+/// An open table as shown in the lobby.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableInfo {
+    pub id: u32,
+    pub name: Option<String>,
+    /// names of the players already waiting
+    pub players: Vec<String>,
+    pub table_size: u32,
+    pub session_length: u32,
+    pub rules: TableRules,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StandingsMessage {
+    /// player ids in the order of `scores`
+    pub ids: Vec<u32>,
+    pub scores: Vec<i32>,
+    /// the hand that was just played
+    pub entry: SkatListEntry,
+    /// how many hands the session lasts
+    pub hands: u32,
+    pub scoring: ScoringMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSummaryMessage {
+    pub skat_list: SkatList,
+    pub scoring: ScoringMode,
+    /// the final scores, in the order of the Skat list's ids
+    pub scores: Vec<i32>,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RamschResultMessage {
+    /// player ids in the seat order of the result
+    pub ids: [u32; 3],
+    pub result: RamschResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BidTurnMessage {
+    /// the highest bid so far, `PASS` if nothing was said yet
+    pub value: u32,
+    /// hearing players answer with `value` to hold it, saying players have to go higher
+    pub hearing: bool,
+}
+
+/// A value said, or held by the hearing player.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewBidMessage {
+    pub id: u32,
+    pub value: u32,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PlayerJoinMessage {
     pub id: u32,
@@ -40,12 +219,24 @@ pub struct PlayerJoinMessage {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenHandMessage {
+    pub id: u32,
+    pub cards: Vec<Card>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardPlayedMessage {
+    pub id: u32,
+    pub card: Card,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Card {
     pub suit: Suit,
     pub rank: Rank,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Suit {
     Hearts,
     Diamonds,
@@ -53,7 +244,7 @@ pub enum Suit {
     Spades,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Rank {
     Seven,
     Eight,
@@ -65,7 +256,22 @@ pub enum Rank {
     Ace,
 }
 
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades];
+}
+
 impl Rank {
+    pub const ALL: [Rank; 8] = [
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+
     pub fn value(&self) -> u32 {
         match self {
             Rank::Seven => 0,
@@ -80,17 +286,12 @@ impl Rank {
     }
 }
 
-pub fn normal_rank_value(rank: &Rank) -> u32 {
-    match rank {
-        Rank::Seven => 0,
-        Rank::Eight => 1,
-        Rank::Nine => 2,
-        Rank::Queen => 3,
-        Rank::King => 4,
-        Rank::Ten => 5,
-        Rank::Ace => 6,
-        Rank::Jack => 6969,
-    }
+/// All 32 cards of a Skat deck, unshuffled.
+pub fn full_deck() -> Vec<Card> {
+    Suit::ALL
+        .iter()
+        .flat_map(|&suit| Rank::ALL.iter().map(move |&rank| Card { suit, rank }))
+        .collect()
 }
 
 pub fn system_time() -> u128 {
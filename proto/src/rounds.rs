@@ -0,0 +1,65 @@
+use crate::GameResult;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// What kind of hand is dealt next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RoundModifier {
+    #[default]
+    Normal,
+    /// the score of the hand counts double
+    Bock,
+    /// nobody bids, the hand is played as a Ramsch
+    Ramsch,
+}
+
+/// Special events at the end of a game that may start a round of Bock or Ramsch hands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoundTrigger {
+    /// the declarer lost a game the defenders doubled with Kontra
+    LostKontra,
+    /// declarer and defenders both ended with 60 card points
+    SixtySixty,
+    /// the game was worth 120 or more, not counting Bock
+    HighValue,
+}
+
+impl RoundTrigger {
+    pub fn triggered_by(result: &GameResult) -> Vec<RoundTrigger> {
+        let value = result.value.value / if result.value.bock { 2 } else { 1 };
+
+        let mut triggers = Vec::new();
+        if result.value.kontra && !result.won {
+            triggers.push(RoundTrigger::LostKontra);
+        }
+        if !result.value.null && result.declarer_points == 60 {
+            triggers.push(RoundTrigger::SixtySixty);
+        }
+        if value >= 120 {
+            triggers.push(RoundTrigger::HighValue);
+        }
+        triggers
+    }
+}
+
+/// The modifiers of the upcoming hands, a round is one hand dealt by every player.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoundQueue {
+    upcoming: VecDeque<RoundModifier>,
+}
+
+impl RoundQueue {
+    pub fn next_modifier(&mut self) -> RoundModifier {
+        self.upcoming.pop_front().unwrap_or_default()
+    }
+
+    /// Queues a round after every round that is already waiting.
+    pub fn queue_round(&mut self, modifier: RoundModifier, hands_per_round: usize) {
+        self.upcoming
+            .extend(std::iter::repeat_n(modifier, hands_per_round));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.upcoming.is_empty()
+    }
+}
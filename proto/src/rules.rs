@@ -0,0 +1,219 @@
+use crate::{Announcements, Card, Rank, Suit};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameType {
+    /// the four Jacks and the given suit are trumps
+    Suit(Suit),
+    /// only the four Jacks are trumps
+    Grand,
+    /// no trumps, the declarer must not take a single trick
+    Null,
+    NullHand,
+    /// Null with the declarer's cards shown to the defenders
+    NullOuvert,
+    NullOuvertHand,
+}
+
+impl GameType {
+    pub fn is_null(&self) -> bool {
+        matches!(
+            self,
+            GameType::Null | GameType::NullHand | GameType::NullOuvert | GameType::NullOuvertHand
+        )
+    }
+
+    /// Whether the game type itself requires playing without the Skat.
+    pub fn is_hand(&self) -> bool {
+        matches!(self, GameType::NullHand | GameType::NullOuvertHand)
+    }
+
+    /// Whether the game type itself requires showing the declarer's cards.
+    pub fn is_ouvert(&self) -> bool {
+        matches!(self, GameType::NullOuvert | GameType::NullOuvertHand)
+    }
+}
+
+impl fmt::Display for GameType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameType::Suit(suit) => write!(f, "{:?}", suit),
+            GameType::Grand => write!(f, "Grand"),
+            GameType::Null => write!(f, "Null"),
+            GameType::NullHand => write!(f, "Null Hand"),
+            GameType::NullOuvert => write!(f, "Null Ouvert"),
+            GameType::NullOuvertHand => write!(f, "Null Ouvert Hand"),
+        }
+    }
+}
+
+/// The game a declarer announces after winning the bidding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Declaration {
+    pub game_type: GameType,
+    /// only used by suit and Grand games, Null variants carry Hand and Ouvert in their type
+    pub announcements: Announcements,
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.game_type)?;
+        let announcements = [
+            (self.announcements.hand, "Hand"),
+            (self.announcements.schneider, "Schneider"),
+            (self.announcements.schwarz, "Schwarz"),
+            (self.announcements.ouvert, "Ouvert"),
+        ];
+        for (_, name) in announcements.iter().filter(|(announced, _)| *announced) {
+            write!(f, " {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+impl Declaration {
+    pub fn is_hand(&self) -> bool {
+        self.game_type.is_hand() || self.announcements.hand
+    }
+
+    pub fn is_ouvert(&self) -> bool {
+        self.game_type.is_ouvert() || self.announcements.ouvert
+    }
+
+    /// Checks that the announcements fit the game type and how the Skat was handled.
+    pub fn validate(&self, picked_up_skat: bool) -> Result<(), String> {
+        let announcements = self.announcements;
+        if self.game_type.is_null() && announcements != Announcements::default() {
+            return Err(String::from(
+                "Null games are announced by their type, not with announcements",
+            ));
+        }
+        let announced = announcements.schneider || announcements.schwarz || announcements.ouvert;
+        if announced && !announcements.hand {
+            return Err(String::from(
+                "Schneider, Schwarz and Ouvert can only be announced in Hand games",
+            ));
+        }
+        if picked_up_skat && self.is_hand() {
+            return Err(String::from(
+                "a Hand game can't be played after picking up the Skat",
+            ));
+        }
+        if !picked_up_skat && !self.is_hand() {
+            return Err(String::from(
+                "a game played without picking up the Skat has to be declared Hand",
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn is_trump(card: &Card, game_type: GameType) -> bool {
+    match game_type {
+        GameType::Suit(trump) => card.rank == Rank::Jack || card.suit == trump,
+        GameType::Grand => card.rank == Rank::Jack,
+        _ => false,
+    }
+}
+
+/// The suit a card has to follow, `None` if it is a trump. Jacks are never part of their printed
+/// suit, except in Null.
+pub fn effective_suit(card: &Card, game_type: GameType) -> Option<Suit> {
+    if is_trump(card, game_type) {
+        None
+    } else {
+        Some(card.suit)
+    }
+}
+
+/// Whether `card` follows the suit (or trump) that `lead` started the trick with.
+pub fn follows_suit(card: &Card, lead: &Card, game_type: GameType) -> bool {
+    effective_suit(card, game_type) == effective_suit(lead, game_type)
+}
+
+/// The strength of a card inside its effective suit, higher beats lower. Jacks beat every other
+/// trump, in the order Clubs > Spades > Hearts > Diamonds.
+pub fn card_strength(card: &Card, game_type: GameType) -> u32 {
+    if game_type.is_null() {
+        return null_rank_value(card.rank);
+    }
+    if card.rank == Rank::Jack {
+        return 10 + jack_rank_value(card.suit);
+    }
+    normal_rank_value(card.rank)
+}
+
+/// Order of the cards in suit and Grand games. The Jacks rank above the Aces, among themselves
+/// their suit decides, see `jack_rank_value`.
+pub fn normal_rank_value(rank: Rank) -> u32 {
+    match rank {
+        Rank::Seven => 0,
+        Rank::Eight => 1,
+        Rank::Nine => 2,
+        Rank::Queen => 3,
+        Rank::King => 4,
+        Rank::Ten => 5,
+        Rank::Ace => 6,
+        Rank::Jack => 7,
+    }
+}
+
+/// Order of the cards in Null games, where the Ten sits between the Nine and the Jack.
+pub fn null_rank_value(rank: Rank) -> u32 {
+    match rank {
+        Rank::Seven => 0,
+        Rank::Eight => 1,
+        Rank::Nine => 2,
+        Rank::Ten => 3,
+        Rank::Jack => 4,
+        Rank::Queen => 5,
+        Rank::King => 6,
+        Rank::Ace => 7,
+    }
+}
+
+pub fn jack_rank_value(suit: Suit) -> u32 {
+    match suit {
+        Suit::Diamonds => 0,
+        Suit::Hearts => 1,
+        Suit::Spades => 2,
+        Suit::Clubs => 3,
+    }
+}
+
+/// Index of the card that takes the (possibly unfinished) trick, the first card is the lead.
+pub fn trick_winner(trick: &[Card], game_type: GameType) -> usize {
+    let lead = &trick[0];
+    trick
+        .iter()
+        .enumerate()
+        .filter(|(_, card)| is_trump(card, game_type) || follows_suit(card, lead, game_type))
+        .max_by_key(|(_, card)| (is_trump(card, game_type), card_strength(card, game_type)))
+        .map(|(i, _)| i)
+        .unwrap()
+}
+
+/// The cards of `hand` that may be played on the current `trick`: a player has to follow the
+/// lead (Jacks counting as trumps), and may play anything if they can't.
+pub fn legal_cards(hand: &[Card], trick: &[Card], game_type: GameType) -> Vec<Card> {
+    let Some(lead) = trick.first() else {
+        return hand.to_vec();
+    };
+
+    let following = hand
+        .iter()
+        .copied()
+        .filter(|card| follows_suit(card, lead, game_type))
+        .collect::<Vec<_>>();
+
+    if following.is_empty() {
+        hand.to_vec()
+    } else {
+        following
+    }
+}
+
+pub fn is_legal_card(card: &Card, hand: &[Card], trick: &[Card], game_type: GameType) -> bool {
+    legal_cards(hand, trick, game_type).contains(card)
+}
@@ -0,0 +1,307 @@
+use crate::{Card, GameType, Rank, Suit, jack_rank_value};
+use serde::{Deserialize, Serialize};
+
+/// What the declarer announced on top of the game type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Announcements {
+    /// played without picking up the Skat
+    pub hand: bool,
+    pub schneider: bool,
+    pub schwarz: bool,
+    pub ouvert: bool,
+}
+
+/// How many of the top trumps the declarer held ("with 2") or was missing ("without 3").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Matadors {
+    pub with: bool,
+    pub count: u32,
+}
+
+/// Breakdown of how the game value was reached.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameValue {
+    pub base_value: u32,
+    /// a Null game, won by taking no trick
+    pub null: bool,
+    /// `None` in Null games, which have a fixed value, unless the Jacks count as matadors there
+    pub matadors: Option<Matadors>,
+    pub hand: bool,
+    pub schneider: bool,
+    pub schneider_announced: bool,
+    pub schwarz: bool,
+    pub schwarz_announced: bool,
+    pub ouvert: bool,
+    pub multiplier: u32,
+    /// the value did not reach the bid, so it was raised to the next multiple of the base value
+    pub overbid: bool,
+    /// a defender doubled the game
+    pub kontra: bool,
+    /// the declarer doubled it again
+    pub re: bool,
+    /// the hand was part of a Bock round and counts double
+    pub bock: bool,
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameResult {
+    pub won: bool,
+    pub declarer_points: u32,
+    pub defender_points: u32,
+    pub declarer_tricks: u32,
+    pub value: GameValue,
+    /// what gets written down for the declarer: the value if won, minus twice the value if lost
+    pub score: i32,
+}
+
+/// Everything about a finished hand that is needed to work out its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayedGame {
+    pub game_type: GameType,
+    pub announcements: Announcements,
+    pub bid: u32,
+    /// the declarer's cards including the Skat, as they were before discarding
+    pub declarer_cards: Vec<Card>,
+    /// card points of the declarer's tricks and the Skat
+    pub declarer_points: u32,
+    pub declarer_tricks: u32,
+    pub kontra: bool,
+    pub re: bool,
+    pub bock: bool,
+    /// house rule: the Jacks count as matadors in Null games
+    pub null_matadors: bool,
+}
+
+pub fn base_value(game_type: GameType) -> u32 {
+    match game_type {
+        GameType::Suit(Suit::Diamonds) => 9,
+        GameType::Suit(Suit::Hearts) => 10,
+        GameType::Suit(Suit::Spades) => 11,
+        GameType::Suit(Suit::Clubs) => 12,
+        GameType::Grand => 24,
+        GameType::Null => 23,
+        GameType::NullHand => 35,
+        GameType::NullOuvert => 46,
+        GameType::NullOuvertHand => 59,
+    }
+}
+
+/// All trumps of the game, highest first.
+pub fn trump_order(game_type: GameType) -> Vec<Card> {
+    if game_type.is_null() {
+        return Vec::new();
+    }
+
+    let mut jacks = Suit::ALL.map(|suit| Card {
+        suit,
+        rank: Rank::Jack,
+    });
+    jacks.sort_by_key(|card| std::cmp::Reverse(jack_rank_value(card.suit)));
+
+    let mut trumps = jacks.to_vec();
+    if let GameType::Suit(suit) = game_type {
+        use Rank::*;
+        trumps.extend([Ace, Ten, King, Queen, Nine, Eight, Seven].map(|rank| Card { suit, rank }));
+    }
+    trumps
+}
+
+pub fn matadors(cards: &[Card], game_type: GameType) -> Matadors {
+    let trumps = trump_order(game_type);
+    let with = trumps.first().is_some_and(|top| cards.contains(top));
+    let count = trumps
+        .iter()
+        .take_while(|trump| cards.contains(trump) == with)
+        .count() as u32;
+
+    Matadors { with, count }
+}
+
+impl PlayedGame {
+    pub fn defender_points(&self) -> u32 {
+        120 - self.declarer_points
+    }
+
+    pub fn evaluate(&self) -> GameResult {
+        let value = self.game_value();
+
+        let won = !value.overbid
+            && if self.game_type.is_null() {
+                self.declarer_tricks == 0
+            } else {
+                self.declarer_points > 60
+                    && (!value.schneider_announced || self.declarer_points >= 90)
+                    && (!value.schwarz_announced || self.declarer_tricks == 10)
+            };
+
+        let score = if won {
+            value.value as i32
+        } else {
+            -2 * value.value as i32
+        };
+
+        GameResult {
+            won,
+            declarer_points: self.declarer_points,
+            defender_points: self.defender_points(),
+            declarer_tricks: self.declarer_tricks,
+            value,
+            score,
+        }
+    }
+
+    fn game_value(&self) -> GameValue {
+        let announcements = self.announcements;
+        let base_value = base_value(self.game_type);
+
+        let mut value = if self.game_type.is_null() {
+            // with the house rule, Null counts the Jacks like a Grand does
+            let matadors = self
+                .null_matadors
+                .then(|| matadors(&self.declarer_cards, GameType::Grand));
+            let multiplier = 1 + matadors.map_or(0, |matadors| matadors.count);
+
+            GameValue {
+                base_value,
+                null: true,
+                matadors,
+                hand: self.game_type.is_hand(),
+                schneider: false,
+                schneider_announced: false,
+                schwarz: false,
+                schwarz_announced: false,
+                ouvert: self.game_type.is_ouvert(),
+                multiplier,
+                overbid: false,
+                kontra: false,
+                re: false,
+                bock: false,
+                value: base_value * multiplier,
+            }
+        } else {
+            let matadors = matadors(&self.declarer_cards, self.game_type);
+            // announcing a higher level always includes the lower ones
+            let schwarz_announced = announcements.schwarz || announcements.ouvert;
+            let schneider_announced = announcements.schneider || schwarz_announced;
+            let schneider = self.declarer_points >= 90 || self.declarer_points <= 30;
+            let schwarz = self.declarer_tricks == 10 || self.declarer_tricks == 0;
+
+            let multiplier = [
+                true,
+                announcements.hand,
+                schneider,
+                schneider_announced,
+                schwarz,
+                schwarz_announced,
+                announcements.ouvert,
+            ]
+            .into_iter()
+            .filter(|&level| level)
+            .count() as u32
+                + matadors.count;
+
+            GameValue {
+                base_value,
+                null: false,
+                matadors: Some(matadors),
+                hand: announcements.hand,
+                schneider,
+                schneider_announced,
+                schwarz,
+                schwarz_announced,
+                ouvert: announcements.ouvert,
+                multiplier,
+                overbid: false,
+                kontra: false,
+                re: false,
+                bock: false,
+                value: base_value * multiplier,
+            }
+        };
+
+        if value.value < self.bid {
+            value.overbid = true;
+            value.multiplier = self.bid.div_ceil(base_value);
+            value.value = base_value * value.multiplier;
+        }
+
+        // Kontra, Re and Bock don't help reaching the bid, so they are applied last
+        value.kontra = self.kontra;
+        value.re = self.kontra && self.re;
+        value.bock = self.bock;
+        for doubled in [value.kontra, value.re, value.bock] {
+            if doubled {
+                value.value *= 2;
+            }
+        }
+        value
+    }
+}
+
+/// A finished Ramsch, where everybody plays for themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RamschGame {
+    /// card points per seat, the Skat counts for whoever took the last trick
+    pub points: [u32; 3],
+    pub tricks: [u32; 3],
+    /// how often the Skat was pushed on unseen in Schieberamsch
+    pub pushes: u32,
+    pub bock: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RamschResult {
+    pub points: [u32; 3],
+    pub tricks: [u32; 3],
+    /// the seats with the most card points, more than one on a tie
+    pub losers: Vec<usize>,
+    /// how many players took no trick at all, each doubles the loss
+    pub jungfrau: u32,
+    /// the seat that took every trick
+    pub durchmarsch: Option<usize>,
+    pub pushes: u32,
+    pub multiplier: u32,
+    /// what gets written down for every seat
+    pub scores: [i32; 3],
+}
+
+impl RamschGame {
+    pub fn evaluate(&self) -> RamschResult {
+        let durchmarsch = self.tricks.iter().position(|&tricks| tricks == 10);
+        let jungfrau = self.tricks.iter().filter(|&&tricks| tricks == 0).count() as u32;
+
+        let (losers, multiplier, scores) = match durchmarsch {
+            Some(seat) => {
+                let multiplier = 2u32.pow(self.bock as u32);
+                let mut scores = [0; 3];
+                scores[seat] = (120 * multiplier) as i32;
+                (Vec::new(), multiplier, scores)
+            }
+            None => {
+                let most_points = self.points.iter().copied().max().unwrap_or_default();
+                let losers = (0..3)
+                    .filter(|&seat| self.points[seat] == most_points)
+                    .collect::<Vec<_>>();
+                let multiplier = 2u32.pow(jungfrau + self.pushes + self.bock as u32);
+
+                let mut scores = [0; 3];
+                for &seat in &losers {
+                    scores[seat] = -((self.points[seat] * multiplier) as i32);
+                }
+                (losers, multiplier, scores)
+            }
+        };
+
+        RamschResult {
+            points: self.points,
+            tricks: self.tricks,
+            losers,
+            jungfrau: if durchmarsch.is_some() { 0 } else { jungfrau },
+            durchmarsch,
+            pushes: self.pushes,
+            multiplier,
+            scores,
+        }
+    }
+}
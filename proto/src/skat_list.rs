@@ -0,0 +1,101 @@
+use crate::{Declaration, GameResult, RamschResultMessage, RoundModifier};
+use serde::{Deserialize, Serialize};
+
+/// How the hands of a session are added up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScoringMode {
+    /// the game values as they are
+    #[default]
+    GameValues,
+    /// the official tournament scoring: 50 extra for the declarer of a won game, 50 less for a
+    /// lost one, and points for every opponent of a lost game
+    SeegerFabian,
+}
+
+impl ScoringMode {
+    /// What each other player at the table gets when the declarer loses, in Seeger-Fabian.
+    pub fn points_per_lost_game(table_size: usize) -> i32 {
+        if table_size == 4 { 30 } else { 40 }
+    }
+}
+
+/// How a hand of a session ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HandOutcome {
+    Game {
+        /// id of the declarer
+        declarer: u32,
+        declaration: Declaration,
+        result: GameResult,
+    },
+    Ramsch(RamschResultMessage),
+}
+
+/// One line of the Skat list (Skatliste).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkatListEntry {
+    /// number of the hand in the session, starting at 1
+    pub hand: u32,
+    /// id of the player that dealt
+    pub dealer: u32,
+    pub modifier: RoundModifier,
+    pub outcome: HandOutcome,
+}
+
+impl SkatListEntry {
+    /// What the hand wrote down for each of the players with the given ids.
+    pub fn scores(&self, ids: &[u32], scoring: ScoringMode) -> Vec<i32> {
+        ids.iter()
+            .map(|&id| match &self.outcome {
+                HandOutcome::Game {
+                    declarer, result, ..
+                } => match scoring {
+                    ScoringMode::GameValues if *declarer == id => result.score,
+                    ScoringMode::GameValues => 0,
+                    ScoringMode::SeegerFabian if *declarer == id => {
+                        result.score + if result.won { 50 } else { -50 }
+                    }
+                    ScoringMode::SeegerFabian if result.won => 0,
+                    ScoringMode::SeegerFabian => ScoringMode::points_per_lost_game(ids.len()),
+                },
+                HandOutcome::Ramsch(ramsch) => ramsch
+                    .ids
+                    .iter()
+                    .position(|&seat_id| seat_id == id)
+                    .map_or(0, |seat| ramsch.result.scores[seat]),
+            })
+            .collect()
+    }
+}
+
+/// Every hand played in a session, with the running score of each player.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkatList {
+    /// ids of everybody taking part in the session
+    pub ids: Vec<u32>,
+    pub entries: Vec<SkatListEntry>,
+}
+
+impl SkatList {
+    pub fn new(ids: Vec<u32>) -> SkatList {
+        SkatList {
+            ids,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, entry: SkatListEntry) {
+        self.entries.push(entry);
+    }
+
+    /// The summed up scores, in the order of `ids`.
+    pub fn totals(&self, scoring: ScoringMode) -> Vec<i32> {
+        let mut totals = vec![0; self.ids.len()];
+        for entry in &self.entries {
+            for (total, score) in totals.iter_mut().zip(entry.scores(&self.ids, scoring)) {
+                *total += score;
+            }
+        }
+        totals
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{
+    Card, CardPlayedMessage, Declaration, GameType, Message, NewBidMessage, OpenHandMessage, PASS,
+    RoundModifier, StandingsMessage, trick_winner,
+};
+use serde::{Deserialize, Serialize};
+
+/// Where in the current hand a table is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GamePhase {
+    /// no hand was dealt yet
+    #[default]
+    Waiting,
+    Bidding,
+    /// the declarer picks up the Skat or plays Hand, and discards
+    Skat,
+    Playing,
+    /// the hand is over, waiting for the next deal
+    HandOver,
+}
+
+/// Everything one seat knows about the running game, enough for a client to rebuild its state
+/// after it missed messages.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub phase: GamePhase,
+    pub modifier: RoundModifier,
+    /// id of the dealer watching the hand at a four-player table
+    pub sitting_out: Option<u32>,
+    /// the seat's own cards
+    pub hand: Vec<Card>,
+    /// the cards of the unfinished trick, in playing order
+    pub trick: Vec<CardPlayedMessage>,
+    /// every bid of the hand in order, passes have the value `PASS`
+    pub bids: Vec<NewBidMessage>,
+    /// id of the declarer, once the game is declared
+    pub declarer: Option<u32>,
+    pub declaration: Option<Declaration>,
+    pub ramsch: bool,
+    pub kontra: bool,
+    pub re: bool,
+    /// the declarer's cards in Ouvert games
+    pub open_hand: Option<OpenHandMessage>,
+    /// how many tricks each player took this hand, as pairs of id and count
+    pub tricks: Vec<(u32, u32)>,
+    /// the running scores of the session, after the last finished hand
+    pub standings: Option<StandingsMessage>,
+    /// the last question sent to the seat that may still be unanswered, like `YourTurn`
+    pub prompt: Option<Message>,
+}
+
+impl GameSnapshot {
+    /// Updates the snapshot with a message that was sent to the seat.
+    pub fn apply(&mut self, msg: &Message) {
+        match msg {
+            Message::RoundModifier(modifier) => {
+                *self = GameSnapshot {
+                    phase: GamePhase::Bidding,
+                    modifier: *modifier,
+                    standings: self.standings.take(),
+                    ..Default::default()
+                };
+            }
+            Message::Redeal => {
+                *self = GameSnapshot {
+                    phase: GamePhase::Bidding,
+                    modifier: self.modifier,
+                    sitting_out: self.sitting_out,
+                    standings: self.standings.take(),
+                    ..Default::default()
+                };
+            }
+            Message::SittingOut(id) => self.sitting_out = Some(*id),
+            Message::DrawCard(card) => self.hand.push(*card),
+            Message::NewBid(bid) => self.bids.push(bid.clone()),
+            Message::Passed(id) => self.bids.push(NewBidMessage {
+                id: *id,
+                value: PASS,
+            }),
+            Message::PlayNormalSolo | Message::PlayNormalDuo => self.phase = GamePhase::Skat,
+            Message::Skat(skat) => self.hand.extend(skat),
+            Message::Discard(cards) => self.hand.retain(|card| !cards.contains(card)),
+            Message::PlayRamsch => {
+                self.phase = GamePhase::Playing;
+                self.ramsch = true;
+            }
+            Message::Trump(declaration) => {
+                self.phase = GamePhase::Playing;
+                self.declaration = Some(*declaration);
+                self.declarer = self
+                    .bids
+                    .iter()
+                    .rev()
+                    .find(|bid| bid.value != PASS)
+                    .map(|bid| bid.id);
+            }
+            Message::KontraAnnounced(_) => self.kontra = true,
+            Message::ReAnnounced(_) => self.re = true,
+            Message::OpenHand(open_hand) => self.open_hand = Some(open_hand.clone()),
+            Message::CardPlayed(played) => self.card_played(played),
+            Message::GameWon(_) | Message::RamschResult(_) => self.phase = GamePhase::HandOver,
+            Message::Standings(standings) => self.standings = Some(standings.clone()),
+            _ => {}
+        }
+
+        match msg {
+            Message::BidTurn(_)
+            | Message::SkatTurn
+            | Message::KontraTurn
+            | Message::ReTurn
+            | Message::YourTurn => self.prompt = Some(msg.clone()),
+            Message::NewBid(_)
+            | Message::Passed(_)
+            | Message::Skat(_)
+            | Message::SkatPushed(_)
+            | Message::PlayRamsch
+            | Message::Trump(_)
+            | Message::KontraAnnounced(_)
+            | Message::ReAnnounced(_)
+            | Message::CardPlayed(_)
+            | Message::GameWon(_)
+            | Message::RamschResult(_)
+            | Message::RoundModifier(_)
+            | Message::Redeal => self.prompt = None,
+            _ => {}
+        }
+    }
+
+    /// The game the tricks are played in, Ramsch is played like a Grand.
+    pub fn game_type(&self) -> GameType {
+        self.declaration
+            .map_or(GameType::Grand, |declaration| declaration.game_type)
+    }
+
+    fn card_played(&mut self, played: &CardPlayedMessage) {
+        self.hand.retain(|card| *card != played.card);
+        if let Some(open_hand) = &mut self.open_hand {
+            open_hand.cards.retain(|card| *card != played.card);
+        }
+
+        self.trick.push(played.clone());
+        if self.trick.len() < 3 {
+            return;
+        }
+
+        let cards = self
+            .trick
+            .iter()
+            .map(|played| played.card)
+            .collect::<Vec<_>>();
+        let winner = self.trick[trick_winner(&cards, self.game_type())].id;
+        match self.tricks.iter_mut().find(|(id, _)| *id == winner) {
+            Some((_, count)) => *count += 1,
+            None => self.tricks.push((winner, 1)),
+        }
+        self.trick.clear();
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{RoundModifier, RoundQueue, RoundTrigger, ScoringMode};
+use serde::{Deserialize, Serialize};
+
+/// House rules of a table, chosen by whoever opens it and followed for the whole session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableRules {
+    /// play Ramsch when everybody passes, instead of dealing again
+    pub ramsch: bool,
+    /// in Ramsch every player may pick up the Skat or push it on unseen (Schieberamsch)
+    pub schieberamsch: bool,
+    /// defenders may double a game with Kontra, and the declarer answer with Re
+    pub kontra: bool,
+    /// events that queue a round of Bock hands, which count double
+    pub bock_triggers: Vec<RoundTrigger>,
+    /// events that queue a round of Ramsch hands
+    pub ramsch_triggers: Vec<RoundTrigger>,
+    /// the Jacks count as matadors in Null games, raising their fixed value like in a Grand
+    pub null_matadors: bool,
+    /// how the Skat list of a session is added up
+    pub scoring: ScoringMode,
+}
+
+impl Default for TableRules {
+    fn default() -> Self {
+        TableRules {
+            ramsch: true,
+            schieberamsch: false,
+            kontra: false,
+            bock_triggers: Vec::new(),
+            ramsch_triggers: Vec::new(),
+            null_matadors: false,
+            scoring: ScoringMode::default(),
+        }
+    }
+}
+
+impl TableRules {
+    /// Queues the rounds that the events of a finished game start, Bock rounds before Ramsch rounds.
+    pub fn queue_rounds(
+        &self,
+        rounds: &mut RoundQueue,
+        triggers: &[RoundTrigger],
+        table_size: usize,
+    ) {
+        for trigger in triggers {
+            if self.bock_triggers.contains(trigger) {
+                rounds.queue_round(RoundModifier::Bock, table_size);
+            }
+        }
+        for trigger in triggers {
+            if self.ramsch_triggers.contains(trigger) {
+                rounds.queue_round(RoundModifier::Ramsch, table_size);
+            }
+        }
+    }
+}
@@ -0,0 +1,792 @@
+use super::*;
+
+#[test]
+fn test_bid_ladder_is_sorted() {
+    assert!(BID_LADDER.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(Some(&18), BID_LADDER.first());
+    assert_eq!(Some(&264), BID_LADDER.last());
+}
+
+#[test]
+fn test_next_bid() {
+    assert_eq!(Some(18), next_bid(PASS));
+    assert_eq!(Some(23), next_bid(22));
+    assert_eq!(Some(27), next_bid(24));
+    assert_eq!(Some(59), next_bid(55));
+    assert_eq!(None, next_bid(264));
+}
+
+#[test]
+fn test_is_valid_bid() {
+    assert!(is_valid_bid(18));
+    assert!(is_valid_bid(46));
+    assert!(!is_valid_bid(PASS));
+    assert!(!is_valid_bid(19));
+    assert!(!is_valid_bid(300));
+}
+
+#[test]
+fn test_bid_roles() {
+    assert_eq!(BidRole::Forehand, BidRole::of_seat(2, 2));
+    assert_eq!(BidRole::Middlehand, BidRole::of_seat(0, 2));
+    assert_eq!(BidRole::Rearhand, BidRole::of_seat(1, 2));
+}
+
+fn card(suit: Suit, rank: Rank) -> Card {
+    Card { suit, rank }
+}
+
+fn trick(cards: [(Suit, Rank); 3]) -> [Card; 3] {
+    cards.map(|(suit, rank)| card(suit, rank))
+}
+
+#[test]
+fn test_jack_order() {
+    use Rank::*;
+    use Suit::*;
+
+    for game_type in [GameType::Suit(Hearts), GameType::Grand] {
+        let cards = trick([(Hearts, Jack), (Clubs, Jack), (Spades, Jack)]);
+        assert_eq!(1, trick_winner(&cards, game_type));
+        let cards = trick([(Diamonds, Jack), (Hearts, Jack), (Spades, Ace)]);
+        assert_eq!(1, trick_winner(&cards, game_type));
+        let cards = trick([(Spades, Jack), (Diamonds, Jack), (Hearts, Jack)]);
+        assert_eq!(0, trick_winner(&cards, game_type));
+    }
+
+    // every rank has a place in the order, the Jack above the Ace
+    assert!(normal_rank_value(Jack) > normal_rank_value(Ace));
+}
+
+#[test]
+fn test_jacks_are_trumps_in_suit_games() {
+    use Rank::*;
+    use Suit::*;
+
+    let game_type = GameType::Suit(Spades);
+    // the Jack of Diamonds beats the Ace of trumps
+    let cards = trick([(Spades, Ace), (Diamonds, Jack), (Spades, Ten)]);
+    assert_eq!(1, trick_winner(&cards, game_type));
+    // a lead Jack of Hearts asks for trumps, not for Hearts
+    let cards = trick([(Hearts, Jack), (Hearts, Ace), (Spades, Seven)]);
+    assert_eq!(0, trick_winner(&cards, game_type));
+    // a Jack played on a Hearts lead trumps it
+    let cards = trick([(Hearts, Ace), (Hearts, Ten), (Hearts, Jack)]);
+    assert_eq!(2, trick_winner(&cards, game_type));
+    // any trump beats the lead suit
+    let cards = trick([(Hearts, Ace), (Spades, Seven), (Hearts, Ten)]);
+    assert_eq!(1, trick_winner(&cards, game_type));
+    // cards that neither follow nor trump can't win
+    let cards = trick([(Hearts, Seven), (Clubs, Ace), (Diamonds, Ace)]);
+    assert_eq!(0, trick_winner(&cards, game_type));
+    // Ten ranks between King and Ace
+    let cards = trick([(Clubs, King), (Clubs, Ten), (Clubs, Queen)]);
+    assert_eq!(1, trick_winner(&cards, game_type));
+    let cards = trick([(Clubs, Ten), (Clubs, Ace), (Clubs, King)]);
+    assert_eq!(1, trick_winner(&cards, game_type));
+}
+
+#[test]
+fn test_grand_has_no_trump_suit() {
+    use Rank::*;
+    use Suit::*;
+
+    let game_type = GameType::Grand;
+    let cards = trick([(Clubs, Nine), (Clubs, Ace), (Hearts, Ace)]);
+    assert_eq!(1, trick_winner(&cards, game_type));
+    let cards = trick([(Clubs, Ace), (Diamonds, Jack), (Clubs, Ten)]);
+    assert_eq!(1, trick_winner(&cards, game_type));
+    assert!(!is_trump(&card(Clubs, Ace), game_type));
+    assert_eq!(None, effective_suit(&card(Clubs, Jack), game_type));
+}
+
+#[test]
+fn test_null_order() {
+    use Rank::*;
+    use Suit::*;
+
+    let game_type = GameType::Null;
+    // Ten ranks between Nine and Jack
+    let cards = trick([(Hearts, Ten), (Hearts, Nine), (Hearts, Seven)]);
+    assert_eq!(0, trick_winner(&cards, game_type));
+    let cards = trick([(Hearts, Ten), (Hearts, Jack), (Hearts, Nine)]);
+    assert_eq!(1, trick_winner(&cards, game_type));
+    // Jacks are part of their printed suit and no trumps
+    let cards = trick([(Spades, Seven), (Clubs, Jack), (Spades, Eight)]);
+    assert_eq!(2, trick_winner(&cards, game_type));
+    assert_eq!(Some(Clubs), effective_suit(&card(Clubs, Jack), game_type));
+}
+
+#[test]
+fn test_trick_order_is_total() {
+    let game_types = Suit::ALL
+        .map(GameType::Suit)
+        .into_iter()
+        .chain([GameType::Grand, GameType::Null]);
+
+    for game_type in game_types {
+        for a in full_deck() {
+            for b in full_deck().into_iter().filter(|b| b != &a) {
+                let a_wins = trick_winner(&[a, b], game_type) == 0;
+                let b_wins = trick_winner(&[b, a], game_type) == 0;
+                if follows_suit(&a, &b, game_type) {
+                    // exactly one of two cards of the same suit is stronger
+                    assert_ne!(a_wins, b_wins, "{:?} {:?} in {:?}", a, b, game_type);
+                } else if is_trump(&a, game_type) {
+                    assert!(a_wins && !b_wins, "{:?} {:?} in {:?}", a, b, game_type);
+                } else if !is_trump(&b, game_type) {
+                    // the lead wins if nobody follows or trumps
+                    assert!(a_wins && b_wins, "{:?} {:?} in {:?}", a, b, game_type);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_legal_cards() {
+    use Rank::*;
+    use Suit::*;
+
+    let hand = [
+        card(Hearts, Jack),
+        card(Hearts, Ace),
+        card(Spades, Seven),
+        card(Clubs, Ten),
+    ];
+
+    // leading is always free
+    assert_eq!(hand.to_vec(), legal_cards(&hand, &[], GameType::Grand));
+
+    // the Jack of Hearts is a trump, not a Hearts card
+    let game_type = GameType::Suit(Spades);
+    let legal = legal_cards(&hand, &[card(Hearts, King)], game_type);
+    assert_eq!(vec![card(Hearts, Ace)], legal);
+    let legal = legal_cards(&hand, &[card(Clubs, Jack)], game_type);
+    assert_eq!(vec![card(Hearts, Jack), card(Spades, Seven)], legal);
+
+    // in Null the Jack belongs to Hearts again
+    let legal = legal_cards(&hand, &[card(Hearts, King)], GameType::Null);
+    assert_eq!(vec![card(Hearts, Jack), card(Hearts, Ace)], legal);
+
+    // a player who can't follow may play anything
+    let legal = legal_cards(&hand, &[card(Diamonds, Seven)], game_type);
+    assert_eq!(hand.to_vec(), legal);
+    assert!(!is_legal_card(
+        &card(Clubs, Ten),
+        &hand,
+        &[card(Hearts, Seven)],
+        GameType::Grand
+    ));
+}
+
+fn played_game(game_type: GameType, declarer_cards: Vec<Card>) -> PlayedGame {
+    PlayedGame {
+        game_type,
+        announcements: Announcements::default(),
+        bid: 18,
+        declarer_cards,
+        declarer_points: 61,
+        declarer_tricks: 5,
+        kontra: false,
+        re: false,
+        bock: false,
+        null_matadors: false,
+    }
+}
+
+#[test]
+fn test_matadors() {
+    use Rank::*;
+    use Suit::*;
+
+    let game_type = GameType::Suit(Hearts);
+    let cards = [
+        card(Clubs, Jack),
+        card(Spades, Jack),
+        card(Diamonds, Jack),
+        card(Hearts, Ace),
+    ];
+    let expected = Matadors {
+        with: true,
+        count: 2,
+    };
+    assert_eq!(expected, matadors(&cards, game_type));
+
+    let cards = [card(Hearts, Ace), card(Hearts, Ten), card(Hearts, King)];
+    let expected = Matadors {
+        with: false,
+        count: 4,
+    };
+    assert_eq!(expected, matadors(&cards, game_type));
+    // in Grand only the Jacks count
+    assert_eq!(expected, matadors(&cards, GameType::Grand));
+
+    // holding every trump of a suit game
+    let all_trumps = trump_order(GameType::Suit(Clubs));
+    let expected = Matadors {
+        with: true,
+        count: 11,
+    };
+    assert_eq!(expected, matadors(&all_trumps, GameType::Suit(Clubs)));
+}
+
+#[test]
+fn test_suit_game_value() {
+    use Rank::*;
+    use Suit::*;
+
+    // with 1, game 2, times Clubs 12
+    let game = played_game(GameType::Suit(Clubs), vec![card(Clubs, Jack)]);
+    let result = game.evaluate();
+    assert!(result.won);
+    assert_eq!(2, result.value.multiplier);
+    assert_eq!(24, result.value.value);
+    assert_eq!(24, result.score);
+
+    // without 2, game 3, Schneider 4, times Diamonds 9, lost
+    let mut game = played_game(GameType::Suit(Diamonds), vec![card(Hearts, Jack)]);
+    game.declarer_points = 25;
+    game.declarer_tricks = 2;
+    let result = game.evaluate();
+    assert!(!result.won);
+    assert!(result.value.schneider);
+    assert_eq!(36, result.value.value);
+    assert_eq!(-72, result.score);
+}
+
+#[test]
+fn test_grand_hand_schwarz() {
+    use Rank::*;
+    use Suit::*;
+
+    let mut game = played_game(
+        GameType::Grand,
+        vec![card(Clubs, Jack), card(Spades, Jack), card(Hearts, Jack)],
+    );
+    game.announcements.hand = true;
+    game.declarer_points = 120;
+    game.declarer_tricks = 10;
+    // with 3, game 4, hand 5, Schneider 6, Schwarz 7
+    let result = game.evaluate();
+    assert!(result.won);
+    assert_eq!(7, result.value.multiplier);
+    assert_eq!(168, result.value.value);
+}
+
+#[test]
+fn test_announcements() {
+    use Rank::*;
+    use Suit::*;
+
+    let mut game = played_game(GameType::Suit(Spades), vec![card(Clubs, Jack)]);
+    game.announcements = Announcements {
+        hand: true,
+        schneider: true,
+        schwarz: false,
+        ouvert: false,
+    };
+    game.declarer_points = 95;
+    // with 1, game 2, hand 3, Schneider 4, announced 5
+    let result = game.evaluate();
+    assert!(result.won);
+    assert_eq!(55, result.value.value);
+
+    // missing the announced Schneider loses the game
+    game.declarer_points = 80;
+    let result = game.evaluate();
+    assert!(!result.won);
+    assert_eq!(44, result.value.value);
+
+    // ouvert includes every announcement below it
+    game.announcements.ouvert = true;
+    game.declarer_points = 120;
+    game.declarer_tricks = 10;
+    let result = game.evaluate();
+    assert!(result.won);
+    assert!(result.value.schwarz_announced);
+    assert_eq!(8 * 11, result.value.value);
+}
+
+#[test]
+fn test_overbid() {
+    use Rank::*;
+    use Suit::*;
+
+    // with 1, game 2 is worth 20 in Hearts, which doesn't reach 24
+    let mut game = played_game(GameType::Suit(Hearts), vec![card(Clubs, Jack)]);
+    game.bid = 24;
+    game.declarer_points = 70;
+    let result = game.evaluate();
+    assert!(!result.won);
+    assert!(result.value.overbid);
+    assert_eq!(30, result.value.value);
+    assert_eq!(-60, result.score);
+}
+
+#[test]
+fn test_kontra_and_re() {
+    use Rank::*;
+    use Suit::*;
+
+    // with 1, game 2, times Hearts 10
+    let mut game = played_game(GameType::Suit(Hearts), vec![card(Clubs, Jack)]);
+    game.kontra = true;
+    let result = game.evaluate();
+    assert!(result.value.kontra);
+    assert_eq!(40, result.value.value);
+
+    game.re = true;
+    game.declarer_points = 50;
+    let result = game.evaluate();
+    assert!(result.value.re);
+    assert_eq!(80, result.value.value);
+    assert_eq!(-160, result.score);
+
+    // Kontra doesn't count towards the bid
+    game.bid = 22;
+    let result = game.evaluate();
+    assert!(result.value.overbid);
+    assert_eq!(120, result.value.value);
+
+    // Re needs a Kontra first
+    game.kontra = false;
+    assert!(!game.evaluate().value.re);
+}
+
+#[test]
+fn test_bock() {
+    use Rank::*;
+    use Suit::*;
+
+    let mut game = played_game(GameType::Suit(Hearts), vec![card(Clubs, Jack)]);
+    game.bock = true;
+    game.kontra = true;
+    let result = game.evaluate();
+    assert!(result.value.bock);
+    assert_eq!(80, result.value.value);
+
+    let result = RamschGame {
+        points: [50, 40, 30],
+        tricks: [4, 3, 3],
+        pushes: 0,
+        bock: true,
+    }
+    .evaluate();
+    assert_eq!(2, result.multiplier);
+    assert_eq!([-100, 0, 0], result.scores);
+}
+
+#[test]
+fn test_round_triggers() {
+    use Rank::*;
+    use Suit::*;
+
+    let mut game = played_game(GameType::Suit(Hearts), vec![card(Clubs, Jack)]);
+    assert!(RoundTrigger::triggered_by(&game.evaluate()).is_empty());
+
+    game.declarer_points = 60;
+    game.kontra = true;
+    assert_eq!(
+        vec![RoundTrigger::LostKontra, RoundTrigger::SixtySixty],
+        RoundTrigger::triggered_by(&game.evaluate())
+    );
+
+    // Grand Hand Schwarz with 4 is worth 24 * 8, Bock doesn't count
+    let mut game = played_game(
+        GameType::Grand,
+        Suit::ALL.map(|suit| card(suit, Jack)).to_vec(),
+    );
+    game.announcements.hand = true;
+    game.declarer_points = 120;
+    game.declarer_tricks = 10;
+    game.bock = true;
+    assert_eq!(
+        vec![RoundTrigger::HighValue],
+        RoundTrigger::triggered_by(&game.evaluate())
+    );
+    game.game_type = GameType::Suit(Diamonds);
+    game.declarer_cards = vec![card(Clubs, Jack)];
+    assert!(RoundTrigger::triggered_by(&game.evaluate()).is_empty());
+}
+
+#[test]
+fn test_round_queue() {
+    let mut rounds = RoundQueue::default();
+    assert_eq!(RoundModifier::Normal, rounds.next_modifier());
+
+    rounds.queue_round(RoundModifier::Bock, 3);
+    rounds.queue_round(RoundModifier::Ramsch, 3);
+    let modifiers = (0..7).map(|_| rounds.next_modifier()).collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            RoundModifier::Bock,
+            RoundModifier::Bock,
+            RoundModifier::Bock,
+            RoundModifier::Ramsch,
+            RoundModifier::Ramsch,
+            RoundModifier::Ramsch,
+            RoundModifier::Normal,
+        ],
+        modifiers
+    );
+    assert!(rounds.is_empty());
+}
+
+#[test]
+fn test_null_values() {
+    for (game_type, value) in [
+        (GameType::Null, 23),
+        (GameType::NullHand, 35),
+        (GameType::NullOuvert, 46),
+        (GameType::NullOuvertHand, 59),
+    ] {
+        let mut game = played_game(game_type, vec![]);
+        game.declarer_tricks = 0;
+        let result = game.evaluate();
+        assert!(result.won);
+        assert_eq!(value, result.value.value);
+        assert_eq!(game_type.is_hand(), result.value.hand);
+        assert_eq!(game_type.is_ouvert(), result.value.ouvert);
+
+        // taking a single trick loses a Null game
+        game.declarer_tricks = 1;
+        assert_eq!(-2 * value as i32, game.evaluate().score);
+    }
+}
+
+#[test]
+fn test_null_matadors() {
+    use Rank::*;
+    use Suit::*;
+
+    // with Clubs and Spades Jack, missing Hearts Jack: "with 2"
+    let cards = vec![card(Clubs, Jack), card(Spades, Jack), card(Diamonds, Jack)];
+    let mut game = played_game(GameType::Null, cards);
+    game.declarer_tricks = 0;
+    assert_eq!(23, game.evaluate().value.value);
+    assert_eq!(None, game.evaluate().value.matadors);
+
+    game.null_matadors = true;
+    let result = game.evaluate();
+    assert!(result.won);
+    assert!(result.value.null);
+    assert_eq!(
+        Some(Matadors {
+            with: true,
+            count: 2
+        }),
+        result.value.matadors
+    );
+    assert_eq!(3 * 23, result.value.value);
+
+    // a Null game with 60 points of "declarer" never counts as 60-60
+    game.declarer_points = 60;
+    assert!(RoundTrigger::triggered_by(&game.evaluate()).is_empty());
+}
+
+#[test]
+fn test_null_variants_have_no_trumps() {
+    use Rank::*;
+    use Suit::*;
+
+    for game_type in [
+        GameType::Null,
+        GameType::NullHand,
+        GameType::NullOuvert,
+        GameType::NullOuvertHand,
+    ] {
+        assert!(!is_trump(&card(Clubs, Jack), game_type));
+        let cards = trick([(Hearts, Ten), (Hearts, Jack), (Clubs, Ace)]);
+        assert_eq!(1, trick_winner(&cards, game_type));
+    }
+}
+
+#[test]
+fn test_declaration_validation() {
+    let declaration = |game_type, announcements| Declaration {
+        game_type,
+        announcements,
+    };
+    let hand = Announcements {
+        hand: true,
+        ..Default::default()
+    };
+    let schneider = Announcements {
+        schneider: true,
+        ..Default::default()
+    };
+
+    assert!(declaration(GameType::Grand, hand).validate(false).is_ok());
+    assert!(declaration(GameType::Grand, hand).validate(true).is_err());
+    assert!(
+        declaration(GameType::NullHand, Announcements::default())
+            .validate(true)
+            .is_err()
+    );
+    assert!(
+        declaration(GameType::NullOuvert, Announcements::default())
+            .validate(true)
+            .is_ok()
+    );
+    assert!(declaration(GameType::Null, hand).validate(false).is_err());
+    // leaving the Skat makes it a Hand game, which has to be declared as one
+    assert!(
+        declaration(GameType::Grand, Announcements::default())
+            .validate(false)
+            .is_err()
+    );
+    assert!(
+        declaration(GameType::Null, Announcements::default())
+            .validate(false)
+            .is_err()
+    );
+    assert!(
+        declaration(GameType::NullHand, Announcements::default())
+            .validate(false)
+            .is_ok()
+    );
+    assert!(
+        declaration(GameType::Suit(Suit::Clubs), schneider)
+            .validate(false)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_declaration_names() {
+    let grand_hand = Declaration {
+        game_type: GameType::Grand,
+        announcements: Announcements {
+            hand: true,
+            ..Default::default()
+        },
+    };
+    assert_eq!("Grand Hand", grand_hand.to_string());
+
+    let null_ouvert = Declaration {
+        game_type: GameType::NullOuvert,
+        announcements: Announcements::default(),
+    };
+    assert_eq!("Null Ouvert", null_ouvert.to_string());
+}
+
+#[test]
+fn test_ramsch() {
+    let result = RamschGame {
+        points: [50, 40, 30],
+        tricks: [4, 3, 3],
+        pushes: 0,
+        bock: false,
+    }
+    .evaluate();
+    assert_eq!(vec![0], result.losers);
+    assert_eq!([-50, 0, 0], result.scores);
+
+    // a tie makes both lose
+    let result = RamschGame {
+        points: [50, 50, 20],
+        tricks: [4, 4, 2],
+        pushes: 0,
+        bock: false,
+    }
+    .evaluate();
+    assert_eq!(vec![0, 1], result.losers);
+    assert_eq!([-50, -50, 0], result.scores);
+}
+
+#[test]
+fn test_ramsch_jungfrau_and_pushes() {
+    let result = RamschGame {
+        points: [70, 50, 0],
+        tricks: [6, 4, 0],
+        pushes: 2,
+        bock: false,
+    }
+    .evaluate();
+    assert_eq!(1, result.jungfrau);
+    assert_eq!(8, result.multiplier);
+    assert_eq!([-560, 0, 0], result.scores);
+}
+
+#[test]
+fn test_ramsch_durchmarsch() {
+    let result = RamschGame {
+        points: [0, 120, 0],
+        tricks: [0, 10, 0],
+        pushes: 1,
+        bock: false,
+    }
+    .evaluate();
+    assert_eq!(Some(1), result.durchmarsch);
+    assert!(result.losers.is_empty());
+    assert_eq!([0, 120, 0], result.scores);
+}
+
+#[test]
+fn test_skat_list_totals() {
+    use Rank::*;
+    use Suit::*;
+
+    let mut skat_list = SkatList::new(vec![4, 5, 6]);
+    // with 1, game 2, times Hearts 10
+    let game = played_game(GameType::Suit(Hearts), vec![card(Clubs, Jack)]);
+    skat_list.push(SkatListEntry {
+        hand: 1,
+        dealer: 4,
+        modifier: RoundModifier::Normal,
+        outcome: HandOutcome::Game {
+            declarer: 5,
+            declaration: Declaration {
+                game_type: game.game_type,
+                announcements: game.announcements,
+            },
+            result: game.evaluate(),
+        },
+    });
+    assert_eq!(vec![0, 20, 0], skat_list.totals(ScoringMode::GameValues));
+
+    let ramsch = RamschGame {
+        points: [30, 40, 50],
+        tricks: [3, 3, 4],
+        pushes: 0,
+        bock: false,
+    };
+    skat_list.push(SkatListEntry {
+        hand: 2,
+        dealer: 5,
+        modifier: RoundModifier::Normal,
+        outcome: HandOutcome::Ramsch(RamschResultMessage {
+            ids: [6, 4, 5],
+            result: ramsch.evaluate(),
+        }),
+    });
+    assert_eq!(vec![0, -30, 0], skat_list.totals(ScoringMode::GameValues));
+}
+
+#[test]
+fn test_seeger_fabian() {
+    use Rank::*;
+    use Suit::*;
+
+    let mut skat_list = SkatList::new(vec![0, 1, 2]);
+    // with 1, game 2, times Hearts 10
+    let mut game = played_game(GameType::Suit(Hearts), vec![card(Clubs, Jack)]);
+    for declarer in [0, 1] {
+        skat_list.push(SkatListEntry {
+            hand: declarer + 1,
+            dealer: 2,
+            modifier: RoundModifier::Normal,
+            outcome: HandOutcome::Game {
+                declarer,
+                declaration: Declaration {
+                    game_type: game.game_type,
+                    announcements: game.announcements,
+                },
+                result: game.evaluate(),
+            },
+        });
+        game.declarer_points = 40;
+    }
+
+    assert_eq!(vec![20, -40, 0], skat_list.totals(ScoringMode::GameValues));
+    // won: 20 + 50, lost: -40 - 50, and 40 for each opponent of the lost game
+    assert_eq!(
+        vec![70 + 40, -90, 40],
+        skat_list.totals(ScoringMode::SeegerFabian)
+    );
+    assert_eq!(30, ScoringMode::points_per_lost_game(4));
+}
+
+#[test]
+fn test_game_snapshot() {
+    use Rank::*;
+    use Suit::*;
+
+    let mut snapshot = GameSnapshot::default();
+    let hand = [card(Clubs, Jack), card(Hearts, Ace), card(Hearts, Seven)];
+    let hearts = Declaration {
+        game_type: GameType::Suit(Hearts),
+        announcements: Announcements::default(),
+    };
+    let played = |id, card| Message::CardPlayed(CardPlayedMessage { id, card });
+
+    snapshot.apply(&Message::RoundModifier(RoundModifier::Bock));
+    for card in hand {
+        snapshot.apply(&Message::DrawCard(card));
+    }
+    snapshot.apply(&Message::BidTurn(BidTurnMessage {
+        value: PASS,
+        hearing: false,
+    }));
+    assert_eq!(GamePhase::Bidding, snapshot.phase);
+    assert!(snapshot.prompt.is_some());
+
+    for msg in [
+        Message::NewBid(NewBidMessage { id: 1, value: 18 }),
+        Message::Passed(0),
+        Message::Passed(2),
+        Message::Trump(hearts),
+        Message::KontraAnnounced(0),
+        played(1, card(Hearts, King)),
+        played(2, card(Hearts, Ten)),
+        Message::YourTurn,
+    ] {
+        snapshot.apply(&msg);
+    }
+    assert_eq!(GamePhase::Playing, snapshot.phase);
+    assert_eq!(Some(1), snapshot.declarer);
+    assert_eq!(Some(hearts), snapshot.declaration);
+    assert!(snapshot.kontra);
+    assert_eq!(3, snapshot.bids.len());
+    assert_eq!(2, snapshot.trick.len());
+    assert_eq!(Some(Message::YourTurn), snapshot.prompt);
+
+    // the own card leaves the hand, and the Jack takes the trick
+    snapshot.apply(&played(0, card(Clubs, Jack)));
+    assert_eq!(vec![card(Hearts, Ace), card(Hearts, Seven)], snapshot.hand);
+    assert!(snapshot.trick.is_empty());
+    assert_eq!(vec![(0, 1)], snapshot.tricks);
+    assert_eq!(None, snapshot.prompt);
+
+    // a new deal starts over but keeps the session scores
+    snapshot.standings = Some(StandingsMessage {
+        ids: vec![0, 1, 2],
+        scores: vec![0, 0, 0],
+        entry: SkatListEntry {
+            hand: 1,
+            dealer: 0,
+            modifier: RoundModifier::Bock,
+            outcome: HandOutcome::Ramsch(RamschResultMessage {
+                ids: [0, 1, 2],
+                result: RamschGame {
+                    points: [40, 40, 40],
+                    tricks: [4, 3, 3],
+                    pushes: 0,
+                    bock: true,
+                }
+                .evaluate(),
+            }),
+        },
+        hands: 36,
+        scoring: ScoringMode::GameValues,
+    });
+    snapshot.apply(&Message::RoundModifier(RoundModifier::Normal));
+    assert!(snapshot.hand.is_empty());
+    assert!(snapshot.tricks.is_empty());
+    assert!(snapshot.standings.is_some());
+
+    // a re-deal throws the cards away, but it is still the same round at the same table
+    snapshot.apply(&Message::RoundModifier(RoundModifier::Bock));
+    snapshot.apply(&Message::SittingOut(3));
+    for card in hand {
+        snapshot.apply(&Message::DrawCard(card));
+    }
+    snapshot.apply(&Message::Passed(1));
+    snapshot.apply(&Message::Redeal);
+    assert!(snapshot.hand.is_empty());
+    assert!(snapshot.bids.is_empty());
+    assert_eq!(GamePhase::Bidding, snapshot.phase);
+    assert_eq!(RoundModifier::Bock, snapshot.modifier);
+    assert_eq!(Some(3), snapshot.sitting_out);
+}
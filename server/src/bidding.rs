@@ -0,0 +1,108 @@
+use crate::seats::Seats;
+use proto::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BidResult {
+    Declarer { seat: usize, bid: u32 },
+    AllPassed,
+}
+
+/// Runs the bidding (Reizen): middlehand says to forehand, rearhand says further to whoever is
+/// left. If nobody said anything, forehand may still open the game or pass it in.
+pub async fn bid(players: &mut Seats, forehand: usize) -> BidResult {
+    for (seat, player) in players.iter_mut().enumerate() {
+        let msg = match BidRole::of_seat(seat, forehand) {
+            BidRole::Forehand => Message::Hear,
+            BidRole::Middlehand => Message::Say,
+            BidRole::Rearhand => Message::SayFurther,
+        };
+        player.send_message(msg).await;
+    }
+
+    let mut highest_bid = PASS;
+    let middlehand = (forehand + 1) % 3;
+    let rearhand = (forehand + 2) % 3;
+
+    let remaining = bid_duel(players, middlehand, forehand, &mut highest_bid).await;
+    let remaining = bid_duel(players, rearhand, remaining, &mut highest_bid).await;
+
+    if highest_bid == PASS {
+        // everybody else passed without saying anything, so the last one left may open
+        let value = ask_bid(players, remaining, highest_bid, false).await;
+        if value == PASS {
+            broadcast_passed(players, remaining).await;
+            return BidResult::AllPassed;
+        }
+        broadcast_new_bid(players, remaining, value).await;
+        highest_bid = value;
+    }
+
+    BidResult::Declarer {
+        seat: remaining,
+        bid: highest_bid,
+    }
+}
+
+/// `sayer` raises until one of the two passes, returns the seat that stays in the bidding.
+async fn bid_duel(
+    players: &mut Seats,
+    sayer: usize,
+    hearer: usize,
+    highest_bid: &mut u32,
+) -> usize {
+    loop {
+        let said = ask_bid(players, sayer, *highest_bid, false).await;
+        if said == PASS {
+            broadcast_passed(players, sayer).await;
+            return hearer;
+        }
+        *highest_bid = said;
+        broadcast_new_bid(players, sayer, said).await;
+
+        let held = ask_bid(players, hearer, *highest_bid, true).await;
+        if held == PASS {
+            broadcast_passed(players, hearer).await;
+            return sayer;
+        }
+        // holding is announced like a bid, so everybody can tell who stays in the bidding
+        broadcast_new_bid(players, hearer, held).await;
+    }
+}
+
+/// Asks a player until they answer with a legal bid.
+async fn ask_bid(players: &mut Seats, seat: usize, value: u32, hearing: bool) -> u32 {
+    let player = &mut players[seat];
+    loop {
+        player
+            .send_message(Message::BidTurn(BidTurnMessage { value, hearing }))
+            .await;
+
+        let answer = player.expect_message_bid().await;
+        let error = if answer == PASS || (hearing && answer == value) {
+            return answer;
+        } else if hearing {
+            format!("you can only hold {} or pass", value)
+        } else if !is_valid_bid(answer) {
+            format!("{} is not a valid bid", answer)
+        } else if answer <= value {
+            format!("you have to bid more than {}", value)
+        } else {
+            return answer;
+        };
+
+        println!("player: {} made an illegal bid: {}", player.name(), error);
+        player.send_message(Message::Error(error)).await;
+    }
+}
+
+async fn broadcast_new_bid(players: &mut Seats, seat: usize, value: u32) {
+    let id = players[seat].id();
+    players
+        .broadcast_message(Message::NewBid(NewBidMessage { id, value }))
+        .await;
+}
+
+async fn broadcast_passed(players: &mut Seats, seat: usize) {
+    let id = players[seat].id();
+    players.broadcast_message(Message::Passed(id)).await;
+}
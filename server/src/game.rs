@@ -1,35 +1,442 @@
+use crate::bidding::{self, BidResult};
 use crate::knows_skat::KnowsSkatRules;
-use std::vec;
+use crate::lobby::LobbyCommand;
+use crate::new_shuffled_deck;
+use crate::seats::Seats;
+use crate::session::Session;
+use proto::*;
+use std::sync::Arc;
+use tokio::sync::{Mutex, mpsc};
 use tokio::task::JoinHandle;
 
+/// The cards of a freshly dealt hand.
+#[derive(Debug, Clone)]
+pub struct Deal {
+    pub hands: [Vec<Card>; 3],
+    pub skat: Vec<Card>,
+    /// the seat that hears first and leads the first trick
+    pub forehand: usize,
+}
+
 pub struct Game {
-    player_1: Box<dyn KnowsSkatRules>,
-    player_2: Box<dyn KnowsSkatRules>,
-    player_3: Box<dyn KnowsSkatRules>,
+    player_ids: Vec<u32>,
+    players: Arc<Mutex<Seats>>,
     task_handle: JoinHandle<()>,
 }
 
 impl Game {
     pub fn new(
-        player_1: Box<dyn KnowsSkatRules>,
-        player_2: Box<dyn KnowsSkatRules>,
-        player_3: Box<dyn KnowsSkatRules>,
+        players: Vec<Box<dyn KnowsSkatRules>>,
+        session_length: u32,
+        rules: TableRules,
+        lobby_cmd_cnl: mpsc::Sender<LobbyCommand>,
     ) -> Game {
-        let task_handle = tokio::spawn(async move {});
+        let player_ids = players.iter().map(|p| p.id()).collect::<Vec<_>>();
+        let players = Arc::new(Mutex::new(Seats::new(players)));
+
+        let task_handle = tokio::spawn({
+            let players = Arc::clone(&players);
+            let player_ids = player_ids.clone();
+            async move {
+                let mut session = Session::new(player_ids.clone(), session_length);
+                session.play(&mut *players.lock().await, &rules).await;
+
+                lobby_cmd_cnl
+                    .send(LobbyCommand::GameFinished { player_ids })
+                    .await
+                    .unwrap_or_else(|_| unreachable!());
+            }
+        });
+
         Game {
-            player_1,
-            player_2,
-            player_3,
+            player_ids,
+            players,
             task_handle,
         }
     }
 
-    pub fn close(self) -> Vec<Box<dyn KnowsSkatRules>> {
+    pub async fn close(self) -> Vec<Box<dyn KnowsSkatRules>> {
         self.task_handle.abort();
-        vec![self.player_1, self.player_2, self.player_3]
+        // waiting for the task to be dropped, so it releases its handle on the players
+        let _ = self.task_handle.await;
+
+        match Arc::try_unwrap(self.players) {
+            Ok(players) => players.into_inner().into_players(),
+            Err(_) => unreachable!("the game task is the only other owner of the players"),
+        }
     }
 
     pub fn has_player_by_id(&self, id: u32) -> bool {
-        return self.player_1.id() == id || self.player_2.id() == id || self.player_3.id() == id;
+        self.player_ids.contains(&id)
+    }
+
+    pub fn player_ids(&self) -> &[u32] {
+        &self.player_ids
+    }
+}
+
+/// Deals and plays a single hand, dealing again if everybody passes and Ramsch isn't played.
+pub async fn play_hand(
+    players: &mut Seats,
+    forehand: usize,
+    modifier: RoundModifier,
+    rules: &TableRules,
+) -> HandOutcome {
+    let bock = modifier == RoundModifier::Bock;
+
+    loop {
+        let deal = deal(players, forehand).await;
+
+        if modifier == RoundModifier::Ramsch {
+            println!("playing Ramsch as part of a Ramsch round");
+            return HandOutcome::Ramsch(ramsch(players, deal, rules, bock).await);
+        }
+
+        match bidding::bid(players, forehand).await {
+            BidResult::Declarer { seat, bid } => {
+                println!(
+                    "player: {} plays with a bid of {}",
+                    players[seat].name(),
+                    bid
+                );
+                let (declaration, result) =
+                    normal_game(players, seat, bid, deal, rules, bock).await;
+                return HandOutcome::Game {
+                    declarer: players[seat].id(),
+                    declaration,
+                    result,
+                };
+            }
+            BidResult::AllPassed if rules.ramsch => {
+                println!("every player passed: playing Ramsch");
+                return HandOutcome::Ramsch(ramsch(players, deal, rules, bock).await);
+            }
+            BidResult::AllPassed => {
+                println!("every player passed: dealing again");
+                players.broadcast_message(Message::Redeal).await;
+            }
+        }
+    }
+}
+
+async fn deal(players: &mut Seats, forehand: usize) -> Deal {
+    let mut cards = new_shuffled_deck();
+    let mut hands: [Vec<Card>; 3] = Default::default();
+
+    for _ in 0..10 {
+        for (player, hand) in players.iter_mut().zip(&mut hands) {
+            let card = cards.pop().unwrap();
+            player.send_message(Message::DrawCard(card)).await;
+            hand.push(card);
+        }
+    }
+
+    Deal {
+        hands,
+        skat: cards,
+        forehand,
+    }
+}
+
+fn turn_order(start: usize) -> impl Iterator<Item = usize> {
+    (0..3).map(move |i| (i + start) % 3)
+}
+
+pub async fn normal_game(
+    players: &mut Seats,
+    solo: usize,
+    bid: u32,
+    deal: Deal,
+    rules: &TableRules,
+    bock: bool,
+) -> (Declaration, GameResult) {
+    let Deal {
+        mut hands,
+        skat,
+        forehand,
+    } = deal;
+
+    for (i, player) in players.iter_mut().enumerate() {
+        if i == solo {
+            player.send_message(Message::PlayNormalSolo).await;
+        } else {
+            player.send_message(Message::PlayNormalDuo).await;
+        }
+    }
+
+    let declarer_cards = [hands[solo].as_slice(), &skat].concat();
+
+    //Skat
+    let player = players[solo].as_mut();
+    player.send_message(Message::SkatTurn).await;
+    let picked_up_skat = player.expect_message_take_skat().await;
+    let skat = if picked_up_skat {
+        player.send_message(Message::Skat(skat.clone())).await;
+        hands[solo].extend(skat);
+        expect_valid_discard(player, &mut hands[solo], true).await
+    } else {
+        skat
+    };
+
+    // the Skat belongs to the declarer, whether it was picked up or not
+    let mut solo_trick = skat.clone();
+    let mut solo_trick_count = 0;
+
+    //Get trump
+    let declaration = expect_valid_declaration(players[solo].as_mut(), picked_up_skat).await;
+    println!("player: {} plays {}", players[solo].name(), declaration);
+    players.broadcast_message(Message::Trump(declaration)).await;
+
+    let (kontra, re) = if rules.kontra {
+        announce_kontra(players, solo).await
+    } else {
+        (false, false)
+    };
+
+    let game_type = declaration.game_type;
+    let mut last_winner = forehand;
+
+    //Play 10 rounds
+    for round in 0..10 {
+        let (trick, winner) = play_trick(players, &mut hands, last_winner, game_type).await;
+        last_winner = winner;
+
+        if last_winner == solo {
+            solo_trick.extend(trick);
+            solo_trick_count += 1;
+        }
+
+        if round == 0 && declaration.is_ouvert() {
+            let open_hand = Message::OpenHand(OpenHandMessage {
+                id: players[solo].id(),
+                cards: hands[solo].clone(),
+            });
+            for (_, player) in players.iter_mut().enumerate().filter(|(i, _)| *i != solo) {
+                player.send_message(open_hand.clone()).await;
+            }
+        }
+
+        if game_type.is_null() && solo_trick_count > 0 {
+            // the declarer lost the Null game with their first trick
+            break;
+        }
+    }
+
+    //Evaluate Winner
+    let result = PlayedGame {
+        game_type,
+        announcements: declaration.announcements,
+        bid,
+        declarer_cards,
+        declarer_points: evaluate_cards_value(&solo_trick),
+        declarer_tricks: solo_trick_count,
+        kontra,
+        re,
+        bock,
+        null_matadors: rules.null_matadors,
+    }
+    .evaluate();
+
+    players.broadcast_message(Message::SkatRevealed(skat)).await;
+    let won_msg = GameWonMessage {
+        id: players[solo].id(),
+        result: result.clone(),
+    };
+    players.broadcast_message(Message::GameWon(won_msg)).await;
+    (declaration, result)
+}
+
+/// Lets the defenders double the game before the first card, and the declarer answer with Re.
+async fn announce_kontra(players: &mut Seats, solo: usize) -> (bool, bool) {
+    for defender in turn_order(solo).skip(1) {
+        players[defender].send_message(Message::KontraTurn).await;
+        if !players[defender].expect_message_kontra().await {
+            continue;
+        }
+        let id = players[defender].id();
+        players
+            .broadcast_message(Message::KontraAnnounced(id))
+            .await;
+
+        players[solo].send_message(Message::ReTurn).await;
+        let re = players[solo].expect_message_re().await;
+        if re {
+            let id = players[solo].id();
+            players.broadcast_message(Message::ReAnnounced(id)).await;
+        }
+        return (true, re);
+    }
+    (false, false)
+}
+
+/// Everybody plays for themselves with only the Jacks as trumps, the most card points lose.
+pub async fn ramsch(
+    players: &mut Seats,
+    deal: Deal,
+    rules: &TableRules,
+    bock: bool,
+) -> RamschResultMessage {
+    let Deal {
+        mut hands,
+        mut skat,
+        forehand,
+    } = deal;
+    players.broadcast_message(Message::PlayRamsch).await;
+
+    let mut pushes = 0;
+    if rules.schieberamsch {
+        for seat in turn_order(forehand) {
+            let player = players[seat].as_mut();
+            player.send_message(Message::SkatTurn).await;
+
+            if player.expect_message_take_skat().await {
+                player.send_message(Message::Skat(skat.clone())).await;
+                hands[seat].extend(skat);
+                skat = expect_valid_discard(player, &mut hands[seat], false).await;
+            } else {
+                pushes += 1;
+                let id = player.id();
+                players.broadcast_message(Message::SkatPushed(id)).await;
+            }
+        }
+    }
+
+    let mut points = [0; 3];
+    let mut tricks = [0; 3];
+    let mut last_winner = forehand;
+
+    for _ in 0..10 {
+        let (trick, winner) = play_trick(players, &mut hands, last_winner, GameType::Grand).await;
+        points[winner] += evaluate_cards_value(&trick);
+        tricks[winner] += 1;
+        last_winner = winner;
+    }
+    // the Skat goes to whoever took the last trick
+    points[last_winner] += evaluate_cards_value(&skat);
+    players.broadcast_message(Message::SkatRevealed(skat)).await;
+
+    let result = RamschGame {
+        points,
+        tricks,
+        pushes,
+        bock,
+    }
+    .evaluate();
+
+    let ids = [players[0].id(), players[1].id(), players[2].id()];
+    let ramsch_result = RamschResultMessage { ids, result };
+    players
+        .broadcast_message(Message::RamschResult(ramsch_result.clone()))
+        .await;
+    ramsch_result
+}
+
+/// Plays one trick starting with `leader`, returns the cards in playing order and the winning seat.
+async fn play_trick(
+    players: &mut Seats,
+    hands: &mut [Vec<Card>; 3],
+    leader: usize,
+    game_type: GameType,
+) -> (Vec<Card>, usize) {
+    let mut trick = vec![];
+
+    for current_player in turn_order(leader) {
+        let card = expect_legal_card(
+            players[current_player].as_mut(),
+            &mut hands[current_player],
+            &trick,
+            game_type,
+        )
+        .await;
+        players
+            .broadcast_message(Message::CardPlayed(CardPlayedMessage {
+                id: players[current_player].id(),
+                card,
+            }))
+            .await;
+        trick.push(card);
+    }
+
+    let winner = (leader + trick_winner(&trick, game_type)) % 3;
+    (trick, winner)
+}
+
+/// Asks the player for the two cards they put into the Skat, until they name two they hold.
+pub async fn expect_valid_discard(
+    player: &mut dyn KnowsSkatRules,
+    hand: &mut Vec<Card>,
+    allow_jacks: bool,
+) -> Vec<Card> {
+    loop {
+        let cards = player.expect_message_discard().await;
+
+        let error = if cards.len() != 2 || cards[0] == cards[1] {
+            "you have to put exactly two cards into the Skat"
+        } else if !cards.iter().all(|card| hand.contains(card)) {
+            "you can only put cards into the Skat that you hold"
+        } else if !allow_jacks && cards.iter().any(|card| card.rank == Rank::Jack) {
+            "Jacks can't be pushed on with the Skat"
+        } else {
+            hand.retain(|card| !cards.contains(card));
+            player.send_message(Message::Discard(cards.clone())).await;
+            return cards;
+        };
+
+        println!(
+            "player: {} made an invalid discard: {}",
+            player.name(),
+            error
+        );
+        player.send_message(Message::Error(error.into())).await;
+    }
+}
+
+/// Asks the declarer for the game they play until the declaration is possible.
+async fn expect_valid_declaration(
+    player: &mut dyn KnowsSkatRules,
+    picked_up_skat: bool,
+) -> Declaration {
+    loop {
+        let declaration = player.expect_message_trump().await;
+        match declaration.validate(picked_up_skat) {
+            Ok(()) => return declaration,
+            Err(error) => {
+                println!(
+                    "player: {} declared an invalid game: {}",
+                    player.name(),
+                    error
+                );
+                player.send_message(Message::Error(error)).await;
+            }
+        }
     }
 }
+
+/// Asks the player for a card until they play one they hold that also follows suit.
+pub async fn expect_legal_card(
+    player: &mut dyn KnowsSkatRules,
+    hand: &mut Vec<Card>,
+    trick: &[Card],
+    game_type: GameType,
+) -> Card {
+    loop {
+        player.send_message(Message::YourTurn).await;
+
+        let card = player.expect_message_play_card().await;
+        if is_legal_card(&card, hand, trick, game_type) {
+            hand.retain(|c| c != &card);
+            return card;
+        }
+
+        println!(
+            "player: {} played an illegal card: {:?}",
+            player.name(),
+            card
+        );
+        player.send_message(Message::IllegalCard(card)).await;
+    }
+}
+
+fn evaluate_cards_value(cards: &[Card]) -> u32 {
+    cards.iter().map(|c| c.rank.value()).sum()
+}
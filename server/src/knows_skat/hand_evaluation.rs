@@ -0,0 +1,227 @@
+use proto::*;
+
+/// How comfortably a hand wins a game, a playable game has a margin of at least zero.
+type Margin = i32;
+
+/// Strength points a suit or Grand game needs to be worth playing.
+const PLAYABLE: Margin = 20;
+/// What the Skat is expected to add when it is picked up.
+const SKAT_ALLOWANCE: Margin = 2;
+/// Extra margin before a game is played without the Skat.
+const HAND_MARGIN: Margin = 6;
+/// Margin lost per card that might be forced to take a trick in Null.
+const NULL_RISK: Margin = 6;
+
+/// The game a hand of ten cards is good for, and what it can bid with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GamePlan {
+    pub game_type: GameType,
+    /// played without picking up the Skat
+    pub hand: bool,
+    /// the value the game is at least worth, so the highest bid the hand can hold
+    pub value: u32,
+}
+
+/// The game types a bot considers, Ouvert games are left to humans.
+fn candidates() -> impl Iterator<Item = GameType> {
+    Suit::ALL
+        .into_iter()
+        .map(GameType::Suit)
+        .chain([GameType::Grand, GameType::Null])
+}
+
+/// The most valuable game the ten dealt cards can play, `None` if the hand should pass.
+pub fn best_plan(cards: &[Card]) -> Option<GamePlan> {
+    candidates()
+        .flat_map(|game_type| {
+            let margin = margin(cards, game_type);
+            let hand = (margin >= HAND_MARGIN).then_some(true);
+            let pickup = (margin + SKAT_ALLOWANCE >= 0).then_some(false);
+            hand.into_iter().chain(pickup).map(move |hand| GamePlan {
+                game_type,
+                hand,
+                value: game_value(cards, game_type, hand),
+            })
+        })
+        .max_by_key(|plan| plan.value)
+}
+
+/// The game to declare with the twelve cards after picking up the Skat, and the two cards to put
+/// away for it. Prefers the safest game that still reaches the bid.
+pub fn choose_game(cards: &[Card], bid: u32) -> (GameType, Vec<Card>) {
+    candidates()
+        .map(|game_type| {
+            let discard = discard(cards, game_type);
+            let kept = cards
+                .iter()
+                .copied()
+                .filter(|card| !discard.contains(card))
+                .collect::<Vec<_>>();
+            let reaches_bid = game_value(cards, game_type, false) >= bid;
+            ((reaches_bid, margin(&kept, game_type)), game_type, discard)
+        })
+        .max_by_key(|(key, ..)| *key)
+        .map(|(_, game_type, discard)| (game_type, discard))
+        .unwrap()
+}
+
+/// The game the cards win most comfortably, for a declarer that has to play what it holds.
+pub fn safest_game(cards: &[Card]) -> GameType {
+    candidates()
+        .max_by_key(|&game_type| margin(cards, game_type))
+        .unwrap()
+}
+
+/// Announces the game, Hand is part of the type in Null.
+pub fn declaration(game_type: GameType, hand: bool) -> Declaration {
+    let game_type = match game_type {
+        GameType::Null if hand => GameType::NullHand,
+        game_type => game_type,
+    };
+    Declaration {
+        game_type,
+        announcements: Announcements {
+            hand: hand && !game_type.is_null(),
+            ..Announcements::default()
+        },
+    }
+}
+
+/// What the game is worth with these cards, counting the matadors they hold or miss.
+fn game_value(cards: &[Card], game_type: GameType, hand: bool) -> u32 {
+    if game_type.is_null() {
+        return base_value(if hand { GameType::NullHand } else { game_type });
+    }
+    let multiplier = matadors(cards, game_type).count + 1 + hand as u32;
+    base_value(game_type) * multiplier
+}
+
+fn margin(cards: &[Card], game_type: GameType) -> Margin {
+    match game_type {
+        GameType::Suit(trump) => suit_strength(cards, trump) - PLAYABLE,
+        GameType::Grand => grand_strength(cards) - PLAYABLE,
+        _ => NULL_RISK - NULL_RISK * null_risks(cards).len() as Margin,
+    }
+}
+
+fn count(cards: &[Card], predicate: impl Fn(&Card) -> bool) -> Margin {
+    cards.iter().filter(|card| predicate(card)).count() as Margin
+}
+
+fn has(cards: &[Card], suit: Suit, rank: Rank) -> bool {
+    cards.contains(&Card { suit, rank })
+}
+
+/// Aces stand, Tens stand behind their Ace and Kings behind both.
+fn side_suit_strength(cards: &[Card], suit: Suit) -> Margin {
+    let suit_cards = count(cards, |card| card.suit == suit && card.rank != Rank::Jack);
+    let ace = has(cards, suit, Rank::Ace);
+    let ten = has(cards, suit, Rank::Ten);
+    let mut strength = 0;
+    if ace {
+        strength += 3;
+    }
+    if ten {
+        strength += match (ace, suit_cards) {
+            (true, _) => 2,
+            (false, 2..) => 1,
+            _ => 0,
+        };
+    }
+    if ace && ten && has(cards, suit, Rank::King) {
+        strength += 1;
+    }
+    strength
+}
+
+fn jack_strength(cards: &[Card]) -> Margin {
+    Suit::ALL
+        .into_iter()
+        .filter(|&suit| has(cards, suit, Rank::Jack))
+        .map(|suit| 3 + jack_rank_value(suit) as Margin / 2)
+        .sum()
+}
+
+/// Counts the trumps, with extra weight on the Jacks and the high ones, the standing cards of the
+/// other suits and the suits that can be trumped right away.
+fn suit_strength(cards: &[Card], trump: Suit) -> Margin {
+    let game_type = GameType::Suit(trump);
+    let trumps = count(cards, |card| is_trump(card, game_type));
+    let high_trumps = [Rank::Ace, Rank::Ten]
+        .into_iter()
+        .filter(|&rank| has(cards, trump, rank))
+        .count() as Margin;
+    let plain_trumps = trumps - count(cards, |card| card.rank == Rank::Jack);
+
+    let side_suits = Suit::ALL.into_iter().filter(|&suit| suit != trump);
+    let mut strength = jack_strength(cards) + 2 * plain_trumps + high_trumps;
+    for suit in side_suits {
+        strength += side_suit_strength(cards, suit);
+        if trumps >= 5 && count(cards, |card| effective_suit(card, game_type) == Some(suit)) == 0 {
+            strength += 2;
+        }
+    }
+    // too few trumps to draw those of the defenders
+    strength - 4 * (5 - trumps).max(0)
+}
+
+/// Grand lives off the Jacks and suits that run from the Ace down.
+fn grand_strength(cards: &[Card]) -> Margin {
+    let jacks = count(cards, |card| card.rank == Rank::Jack);
+    let mut strength = jack_strength(cards) + jacks;
+    for suit in Suit::ALL {
+        strength += side_suit_strength(cards, suit);
+    }
+    strength - 8 * (2 - jacks).max(0)
+}
+
+/// The cards the defenders can force to take a trick: in every suit, the `n`th lowest card is
+/// safe as long as at most `2n` cards could be below it.
+fn null_risks(cards: &[Card]) -> Vec<Card> {
+    let mut risks = Vec::new();
+    for suit in Suit::ALL {
+        let mut suit_cards = cards
+            .iter()
+            .copied()
+            .filter(|card| card.suit == suit)
+            .collect::<Vec<_>>();
+        suit_cards.sort_by_key(|card| null_rank_value(card.rank));
+        for (n, card) in suit_cards.into_iter().enumerate() {
+            if null_rank_value(card.rank) > 2 * n as u32 {
+                risks.push(card);
+            }
+        }
+    }
+    risks
+}
+
+/// The two cards to put into the Skat for the game: in Null the riskiest ones, otherwise side
+/// cards of short suits without an Ace, the ones worth the most points first.
+fn discard(cards: &[Card], game_type: GameType) -> Vec<Card> {
+    let mut candidates = cards.to_vec();
+    if game_type.is_null() {
+        let risks = null_risks(cards);
+        candidates.sort_by_key(|card| {
+            (
+                !risks.contains(card),
+                std::cmp::Reverse(null_rank_value(card.rank)),
+            )
+        });
+    } else {
+        let suit_len = |suit| count(cards, |card| effective_suit(card, game_type) == Some(suit));
+        candidates.sort_by_key(|card| {
+            let trump = is_trump(card, game_type);
+            let standing = card.rank == Rank::Ace;
+            (
+                trump,
+                standing,
+                !trump && has(cards, card.suit, Rank::Ace),
+                suit_len(card.suit),
+                std::cmp::Reverse(card.rank.value()),
+                card_strength(card, game_type),
+            )
+        });
+    }
+    candidates.truncate(2);
+    candidates
+}
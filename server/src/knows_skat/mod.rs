@@ -5,12 +5,15 @@ use proto::*;
 use std::any::Any;
 use std::fmt::Debug;
 
+pub mod hand_evaluation;
 pub mod npc;
 pub mod player;
+pub mod random_npc;
+pub mod search_npc;
 
 #[async_trait]
 pub trait KnowsSkatRules: Debug + Send + Any {
-    #[message_types_trait(Trump(Suit), PlayCard(Card), Bid(i32))]
+    #[message_types_trait(Trump(Declaration), PlayCard(Card), Bid(u32), TakeSkat(bool), Discard(Vec<Card>), Kontra(bool), Re(bool))]
     async fn expect_message(&mut self) -> Message;
     async fn send_message(&mut self, msg: Message);
     fn into_any(self: Box<Self>) -> Box<dyn Any>;
@@ -1,39 +1,299 @@
 use crate::knows_skat::KnowsSkatRules;
+use crate::knows_skat::hand_evaluation::{self, GamePlan};
 use async_trait::async_trait;
 use macros::message_types;
 use proto::*;
-use std::{collections::VecDeque, fmt};
+use std::fmt;
 
+/// A bot that follows the game through the messages sent to it and answers with simple rules:
+/// it bids what its hand is worth, always plays a legal card, leads its Aces, takes valuable
+/// tricks and gives points to its partner (schmieren).
+#[allow(clippy::upper_case_acronyms)]
 pub struct NPC {
     id: u32,
     name: String,
-    msg_stack: VecDeque<Message>,
+    /// what the bot knows about the running hand
+    game: GameSnapshot,
+    /// the bot won the bidding and declares the game
+    solo: bool,
+    /// Hand games can only be declared without it
+    picked_up_skat: bool,
+    /// the game chosen together with the discarded cards, declared right after
+    picked_game: Option<GameType>,
 }
 
 impl NPC {
     pub fn new(id: u32) -> Self {
-        use proto::{Rank::*, Suit::*};
-        let msg_stack = vec![(Spades, Ace), (Diamonds, Ace), (Clubs, Ace), (Hearts, Ace)]
-            .into_iter()
-            .map(|(suit, rank)| Message::PlayCard(Card { suit, rank }))
-            .collect();
-
         Self {
             id,
             name: String::from("NPC"),
-            msg_stack,
+            game: GameSnapshot::default(),
+            solo: false,
+            picked_up_skat: false,
+            picked_game: None,
+        }
+    }
+
+    pub fn named(id: u32, name: String) -> Self {
+        Self {
+            name,
+            ..NPC::new(id)
+        }
+    }
+
+    /// What the bot knows about the running hand.
+    pub fn game(&self) -> &GameSnapshot {
+        &self.game
+    }
+
+    /// The answer to the last question the bot was asked. The Skat cards and the declaration are
+    /// never asked for explicitly, they follow picking up the Skat and the bidding.
+    fn answer(&mut self) -> Message {
+        match self.game.prompt.take() {
+            Some(Message::BidTurn(turn)) => Message::Bid(self.bid(&turn)),
+            // in Schieberamsch the Skat is pushed on unseen
+            Some(Message::SkatTurn) if self.game.ramsch => Message::TakeSkat(false),
+            Some(Message::SkatTurn) => Message::TakeSkat(!self.plan().is_some_and(|p| p.hand)),
+            Some(Message::KontraTurn) => Message::Kontra(false),
+            Some(Message::ReTurn) => Message::Re(false),
+            Some(Message::YourTurn) => Message::PlayCard(self.choose_card()),
+            _ if self.game.hand.len() == 12 => Message::Discard(self.discard()),
+            _ => Message::Trump(self.declaration()),
+        }
+    }
+
+    fn game_type(&self) -> GameType {
+        self.game.game_type()
+    }
+
+    /// What the dealt cards are good for, judged before the Skat is picked up.
+    fn plan(&self) -> Option<GamePlan> {
+        hand_evaluation::best_plan(&self.game.hand)
+    }
+
+    /// Says or holds as long as the planned game is worth the bid.
+    fn bid(&self, turn: &BidTurnMessage) -> u32 {
+        let limit = self.plan().map_or(PASS, |plan| plan.value);
+        let answer = if turn.hearing {
+            Some(turn.value)
+        } else {
+            next_bid(turn.value)
+        };
+        answer.filter(|&value| value <= limit).unwrap_or(PASS)
+    }
+
+    fn discard(&mut self) -> Vec<Card> {
+        if self.game.ramsch {
+            return self.ramsch_discard();
+        }
+        let bid = self
+            .game
+            .bids
+            .iter()
+            .map(|bid| bid.value)
+            .max()
+            .unwrap_or(PASS);
+        let (game_type, discard) = hand_evaluation::choose_game(&self.game.hand, bid);
+        self.picked_game = Some(game_type);
+        discard
+    }
+
+    /// The two cards worth the least, Jacks can't be pushed on.
+    fn ramsch_discard(&self) -> Vec<Card> {
+        let mut cards = self
+            .game
+            .hand
+            .iter()
+            .copied()
+            .filter(|card| card.rank != Rank::Jack)
+            .collect::<Vec<_>>();
+        cards.sort_by_key(|card| (card.rank.value(), normal_rank_value(card.rank)));
+        cards.into_iter().take(2).collect()
+    }
+
+    /// The game picked with the Skat, or the Hand game the dealt cards were bid on.
+    fn declaration(&mut self) -> Declaration {
+        let game_type = self
+            .picked_game
+            .take()
+            .or_else(|| self.plan().map(|plan| plan.game_type))
+            .unwrap_or_else(|| hand_evaluation::safest_game(&self.game.hand));
+        hand_evaluation::declaration(game_type, !self.picked_up_skat)
+    }
+
+    /// The id of the player on the bot's side, only defenders have one.
+    fn partner(&self) -> Option<u32> {
+        if self.game.ramsch || self.solo {
+            return None;
+        }
+        let declarer = self.game.declarer?;
+        let ids = self
+            .game
+            .bids
+            .iter()
+            .map(|bid| bid.id)
+            .chain(self.game.trick.iter().map(|played| played.id));
+        let mut partner = None;
+        for id in ids {
+            if id != self.id && id != declarer {
+                partner = Some(id);
+            }
+        }
+        partner
+    }
+
+    /// The card the rules pick for the trick.
+    pub fn choose_card(&self) -> Card {
+        let game_type = self.game_type();
+        let trick = self
+            .game
+            .trick
+            .iter()
+            .map(|played| played.card)
+            .collect::<Vec<_>>();
+        let legal = legal_cards(&self.game.hand, &trick, game_type);
+
+        if trick.is_empty() {
+            return self.lead(&legal);
+        }
+
+        let wins = |card: &Card| {
+            let mut trick = trick.clone();
+            trick.push(*card);
+            trick_winner(&trick, game_type) == trick.len() - 1
+        };
+        let (winning, losing): (Vec<Card>, Vec<Card>) = legal.iter().partition(|card| wins(card));
+
+        // in Ramsch and as Null declarer every trick is one too many
+        let avoid_tricks = self.game.ramsch || (game_type.is_null() && self.solo);
+        if avoid_tricks {
+            return highest(&losing, game_type).unwrap_or_else(|| lowest(&winning, game_type));
+        }
+        if game_type.is_null() {
+            return lowest(&legal, game_type);
+        }
+
+        let leader = self.game.trick[trick_winner(&trick, game_type)].id;
+        if self.partner() == Some(leader) && (trick.len() == 2 || !self.declarer_can_follow()) {
+            return schmieren(&legal, game_type);
+        }
+
+        let points = trick.iter().map(|card| card.rank.value()).sum::<u32>();
+        let worth_it = points >= 10 || trick.len() == 2;
+        match lowest_winner(&winning, game_type) {
+            Some(card) if worth_it || !is_trump(&card, game_type) => card,
+            _ => cheapest(&losing, game_type).unwrap_or_else(|| lowest(&legal, game_type)),
         }
     }
+
+    /// Whether the declarer still has to play to the trick, and might take it.
+    fn declarer_can_follow(&self) -> bool {
+        self.game
+            .declarer
+            .is_some_and(|declarer| self.game.trick.iter().all(|played| played.id != declarer))
+    }
+
+    fn lead(&self, legal: &[Card]) -> Card {
+        let game_type = self.game_type();
+        if self.game.ramsch || game_type.is_null() {
+            return lowest(legal, game_type);
+        }
+
+        let aces = legal
+            .iter()
+            .copied()
+            .filter(|card| card.rank == Rank::Ace && !is_trump(card, game_type))
+            .collect::<Vec<_>>();
+        if let Some(ace) = aces.first() {
+            return *ace;
+        }
+
+        // the declarer draws the trumps of the defenders
+        if self.solo
+            && let Some(trump) = highest(
+                &legal
+                    .iter()
+                    .copied()
+                    .filter(|card| is_trump(card, game_type))
+                    .collect::<Vec<_>>(),
+                game_type,
+            )
+        {
+            return trump;
+        }
+        cheapest(legal, game_type).unwrap_or_else(|| lowest(legal, game_type))
+    }
+}
+
+/// Orders cards by their points first, and their strength in the game second.
+fn card_order(card: &Card, game_type: GameType) -> (bool, u32, u32) {
+    (
+        is_trump(card, game_type),
+        card.rank.value(),
+        card_strength(card, game_type),
+    )
+}
+
+fn lowest(cards: &[Card], game_type: GameType) -> Card {
+    *cards
+        .iter()
+        .min_by_key(|card| card_strength(card, game_type))
+        .expect("a player always holds a card when it is their turn")
+}
+
+fn highest(cards: &[Card], game_type: GameType) -> Option<Card> {
+    cards
+        .iter()
+        .copied()
+        .max_by_key(|card| card_strength(card, game_type))
+}
+
+/// The card that gives away the fewest points, saving trumps.
+fn cheapest(cards: &[Card], game_type: GameType) -> Option<Card> {
+    cards
+        .iter()
+        .copied()
+        .min_by_key(|card| card_order(card, game_type))
+}
+
+/// The weakest card that still takes the trick.
+fn lowest_winner(cards: &[Card], game_type: GameType) -> Option<Card> {
+    cards
+        .iter()
+        .copied()
+        .min_by_key(|card| (is_trump(card, game_type), card_strength(card, game_type)))
+}
+
+/// The card with the most points for the partner's trick, Jacks are kept back.
+fn schmieren(cards: &[Card], game_type: GameType) -> Card {
+    cards
+        .iter()
+        .copied()
+        .filter(|card| card.rank != Rank::Jack)
+        .max_by_key(|card| (card.rank.value(), !is_trump(card, game_type)))
+        .unwrap_or_else(|| lowest(cards, game_type))
 }
 
 #[async_trait]
 impl KnowsSkatRules for NPC {
-    #[message_types(Trump(Suit), PlayCard(Card), Bid(i32))]
+    #[message_types(Trump(Declaration), PlayCard(Card), Bid(u32), TakeSkat(bool), Discard(Vec<Card>), Kontra(bool), Re(bool))]
     async fn expect_message(&mut self) -> Message {
-        self.msg_stack.pop_front().unwrap_or_default()
+        self.answer()
     }
 
-    async fn send_message(&mut self, _msg: Message) {}
+    async fn send_message(&mut self, msg: Message) {
+        match msg {
+            Message::RoundModifier(_) | Message::Redeal => {
+                self.solo = false;
+                self.picked_up_skat = false;
+                self.picked_game = None;
+            }
+            Message::Skat(_) => self.picked_up_skat = true,
+            Message::PlayNormalSolo => self.solo = true,
+            _ => {}
+        }
+        self.game.apply(&msg);
+    }
 
     fn name(&self) -> String {
         self.name.clone()
@@ -1,4 +1,4 @@
-use crate::knows_skat::KnowsSkatRules;
+use crate::knows_skat::{KnowsSkatRules, npc::NPC};
 use crate::lobby::LobbyCommand;
 use async_trait::async_trait;
 use macros::message_types;
@@ -6,6 +6,7 @@ use proto::*;
 use std::fmt;
 use std::result::Result::Ok;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::tcp::OwnedReadHalf;
 use tokio::net::{TcpStream, tcp};
@@ -16,12 +17,109 @@ use tokio::time::{Duration, sleep};
 pub struct Player {
     pub id: u32,
     pub name: String,
-    tcp_writer: tcp::OwnedWriteHalf,
+    resume_token: String,
+    connection: Connection,
+    /// connections of the same client that took the seat back after a disconnect, the latest one
+    /// wins
+    reconnects: mpsc::UnboundedReceiver<Connection>,
+    reconnects_tx: mpsc::UnboundedSender<Connection>,
+    seat_state: Arc<Mutex<SeatState>>,
+    /// plays the seat while the connection is lost, until the client takes it back
+    stand_in: Option<Box<dyn KnowsSkatRules>>,
+    lobby_cmd_cnl: mpsc::Sender<LobbyCommand>,
+}
+
+/// The network side of a player, which is swapped out when the client reconnects.
+pub struct Connection {
+    /// the player id the network threads report to the lobby
+    id: Arc<AtomicU32>,
+    /// shared with the keep-alive thread, which shuts it down when the client stops answering
+    tcp_writer: Arc<Mutex<tcp::OwnedWriteHalf>>,
     ip_addr: String,
     game_messages: mpsc::Receiver<Message>,
     network_handle: JoinHandle<()>,
     keep_alive_handle: JoinHandle<()>,
-    lobby_cmd_cnl: mpsc::Sender<LobbyCommand>,
+    /// the client can't be reached anymore
+    lost: bool,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.network_handle.abort();
+        self.keep_alive_handle.abort();
+    }
+}
+
+/// What the lobby keeps of a player to hand its seat to a reconnecting client, even while the
+/// player itself is held by a running game.
+#[derive(Debug, Clone)]
+pub struct ResumeHandle {
+    pub id: u32,
+    pub resume_token: String,
+    reconnects: mpsc::UnboundedSender<Connection>,
+    seat_state: Arc<Mutex<SeatState>>,
+}
+
+/// What a reconnecting client is sent to rebuild its table.
+#[derive(Debug, Default)]
+struct SeatState {
+    /// who sits at the table and how it is set up
+    table: Vec<Message>,
+    snapshot: GameSnapshot,
+    /// everything sent since the current hand was dealt, for a stand-in to catch up
+    history: Vec<Message>,
+}
+
+impl SeatState {
+    fn record(&mut self, msg: &Message) {
+        match msg {
+            Message::PlayerJoin(_)
+            | Message::PlayerLeave(_)
+            | Message::TableSize(_)
+            | Message::SessionLength(_)
+            | Message::InviteCode(_)
+            | Message::StartGame => self.table.push(msg.clone()),
+            Message::BackToLobby => *self = SeatState::default(),
+            msg => {
+                if let Message::RoundModifier(_) = msg {
+                    self.history.clear();
+                }
+                self.history.push(msg.clone());
+                self.snapshot.apply(msg);
+            }
+        }
+    }
+
+    fn replay(&self) -> Vec<Message> {
+        let snapshot = Message::GameSnapshot(Box::new(self.snapshot.clone()));
+        self.table.iter().cloned().chain([snapshot]).collect()
+    }
+}
+
+impl ResumeHandle {
+    /// Moves the connection of `new_player` into the seat of this handle's player. The client
+    /// gets its id, the table and a snapshot of the game right away, the seat picks up the
+    /// connection with the next message it sends or reads. Fails if the new connection broke
+    /// during the replay, or the seat is gone, the seat stays disconnected then.
+    pub async fn resume(&self, new_player: Player) -> std::io::Result<()> {
+        let Player { connection, .. } = new_player;
+        // holding the state, so the seat can't send anything that would miss the replay
+        let seat_state = self.seat_state.lock().await;
+
+        let confirm = Message::ConfirmJoin(ConfirmJoinMessage {
+            id: self.id,
+            resume_token: self.resume_token.clone(),
+        });
+        for msg in [confirm].into_iter().chain(seat_state.replay()) {
+            connection.write_message(&msg).await?;
+        }
+
+        connection.id.store(self.id, Ordering::Relaxed);
+        // never waits for the seat, which takes the state before it picks up connections
+        self.reconnects
+            .send(connection)
+            .map_err(|_| std::io::Error::other("the seat was closed"))
+    }
 }
 
 impl fmt::Debug for Player {
@@ -29,34 +127,43 @@ impl fmt::Debug for Player {
         f.debug_struct("Player")
             .field("id", &self.id)
             .field("name", &self.name)
-            .field("ip_addr", &self.ip_addr)
+            .field("ip_addr", &self.connection.ip_addr)
             .finish()
     }
 }
 
-impl Drop for Player {
-    fn drop(&mut self) {
-        self.network_handle.abort();
-        self.keep_alive_handle.abort();
-    }
-}
-
 #[async_trait]
 impl KnowsSkatRules for Player {
-    #[message_types(Trump(Suit), PlayCard(Card), Bid(i32))]
+    #[message_types(Trump(Declaration), PlayCard(Card), Bid(u32), TakeSkat(bool), Discard(Vec<Card>), Kontra(bool), Re(bool))]
     async fn expect_message(&mut self) -> Message {
         self.read_message().await
     }
 
     async fn send_message(&mut self, msg: Message) {
         println!("sending message: {:?}, to Player: {}", msg, self.name);
-        let mut serialized = serde_json::to_string(&msg).unwrap();
-        serialized.push('\n');
-        if let Err(_) = self.tcp_writer.write_all(&serialized.as_bytes()).await {
+        let seat_state = Arc::clone(&self.seat_state);
+        let mut seat_state = seat_state.lock().await;
+        seat_state.record(&msg);
+        while let Ok(connection) = self.reconnects.try_recv() {
+            self.take_seat_back(connection);
+        }
+        let sent = if self.connection.lost {
+            Ok(())
+        } else {
+            self.connection.write_message(&msg).await
+        };
+        drop(seat_state);
+
+        if let Some(stand_in) = &mut self.stand_in {
+            stand_in.send_message(msg).await;
+        }
+
+        if sent.is_err() {
             println!(
                 "player: {}, failed to send a Message: disconnecting",
                 self.name
             );
+            self.connection.lost = true;
             self.disconnect().await;
         }
     }
@@ -74,32 +181,71 @@ impl KnowsSkatRules for Player {
     }
 }
 
+impl Connection {
+    async fn write_message(&self, msg: &Message) -> std::io::Result<()> {
+        let mut serialized = serde_json::to_string(msg).unwrap();
+        serialized.push('\n');
+        self.tcp_writer
+            .lock()
+            .await
+            .write_all(serialized.as_bytes())
+            .await
+    }
+}
+
 impl Player {
     pub fn new(
         tcp_stream: TcpStream,
         id: u32,
+        resume_token: String,
         ip_addr: String,
         lobby_cmd_cnl: mpsc::Sender<LobbyCommand>,
     ) -> Self {
         let (tcp_reader, tcp_writer) = TcpStream::into_split(tcp_stream);
+        let tcp_writer = Arc::new(Mutex::new(tcp_writer));
 
         let (game_messages_tx, game_messages) = mpsc::channel::<Message>(100);
+        let (reconnects_tx, reconnects) = mpsc::unbounded_channel::<Connection>();
 
-        let (network_handle, keep_alive_handle) =
-            Self::spawn_network_treads(id, tcp_reader, lobby_cmd_cnl.clone(), game_messages_tx);
+        let network_id = Arc::new(AtomicU32::new(id));
+        let (network_handle, keep_alive_handle) = Self::spawn_network_treads(
+            Arc::clone(&network_id),
+            tcp_reader,
+            Arc::clone(&tcp_writer),
+            lobby_cmd_cnl.clone(),
+            game_messages_tx,
+        );
 
         Player {
-            id: id as u32,
+            id,
             name: String::from(""),
-            tcp_writer,
-            ip_addr: ip_addr,
-            game_messages,
-            network_handle,
-            keep_alive_handle,
+            resume_token,
+            connection: Connection {
+                id: network_id,
+                tcp_writer,
+                ip_addr,
+                game_messages,
+                network_handle,
+                keep_alive_handle,
+                lost: false,
+            },
+            reconnects,
+            reconnects_tx,
+            seat_state: Arc::new(Mutex::new(SeatState::default())),
+            stand_in: None,
             lobby_cmd_cnl,
         }
     }
 
+    pub fn resume_handle(&self) -> ResumeHandle {
+        ResumeHandle {
+            id: self.id,
+            resume_token: self.resume_token.clone(),
+            reconnects: self.reconnects_tx.clone(),
+            seat_state: Arc::clone(&self.seat_state),
+        }
+    }
+
     async fn disconnect(&mut self) {
         println!("player: {} wants to disconnect", self.name);
         self.lobby_cmd_cnl
@@ -108,19 +254,56 @@ impl Player {
             .unwrap_or_else(|_| unreachable!());
     }
 
+    /// Reads the next answer of the client, or of the stand-in while the client is away.
     async fn read_message(&mut self) -> Message {
-        match self.game_messages.recv().await {
-            Some(msg) => msg,
-            None => loop {
-                //this only happens when the player is about to get deleted
-                sleep(Duration::from_millis(1)).await;
-            },
+        loop {
+            if self.connection.lost && self.stand_in.is_none() {
+                self.stand_in = Some(self.new_stand_in().await);
+            }
+
+            let reconnect = match &mut self.stand_in {
+                Some(stand_in) => tokio::select! {
+                    msg = stand_in.expect_message() => return msg,
+                    Some(connection) = self.reconnects.recv() => connection,
+                },
+                None => tokio::select! {
+                    msg = self.connection.game_messages.recv() => match msg {
+                        Some(msg) => return msg,
+                        // the network threads stopped, the client is gone
+                        None => {
+                            self.connection.lost = true;
+                            continue;
+                        }
+                    },
+                    Some(connection) = self.reconnects.recv() => connection,
+                },
+            };
+            self.take_seat_back(reconnect);
+        }
+    }
+
+    fn take_seat_back(&mut self, connection: Connection) {
+        self.connection = connection;
+        if self.stand_in.take().is_some() {
+            println!("player: {} takes their seat back", self.name);
         }
     }
 
+    /// An NPC that knows everything the seat was told this hand.
+    async fn new_stand_in(&mut self) -> Box<dyn KnowsSkatRules> {
+        println!("an NPC stands in for player: {}", self.name);
+        let history = self.seat_state.lock().await.history.clone();
+        let mut stand_in = NPC::new(self.id);
+        for msg in history {
+            stand_in.send_message(msg).await;
+        }
+        Box::new(stand_in)
+    }
+
     fn spawn_network_treads(
-        id: u32,
+        id: Arc<AtomicU32>,
         tcp_reader: OwnedReadHalf,
+        tcp_writer: Arc<Mutex<tcp::OwnedWriteHalf>>,
         lobby_cmd_cnl: mpsc::Sender<LobbyCommand>,
         game_messages_tx: mpsc::Sender<Message>,
     ) -> (JoinHandle<()>, JoinHandle<()>) {
@@ -128,6 +311,7 @@ impl Player {
         let last_keep_alive = Arc::new(Mutex::new(system_time()));
 
         let nework_handle = tokio::spawn({
+            let id = Arc::clone(&id);
             let last_keep_alive = Arc::clone(&last_keep_alive);
             let lobby_cmd_cnl = lobby_cmd_cnl.clone();
 
@@ -140,10 +324,9 @@ impl Player {
                             println!("reading from tcp_stream failed! : {}", e);
                         }
                     }
-                    let msg: Option<Message> = match serde_json::from_str(&buf) {
-                        Ok(msg) => Some(msg),
-                        Err(_) => None,
-                    };
+                    let msg: Option<Message> = serde_json::from_str(&buf).ok();
+                    // the id changes when this connection takes back the seat of another one
+                    let id = id.load(Ordering::Relaxed);
                     match msg {
                         Some(Message::KeepAlive(time_stamp)) => {
                             *last_keep_alive.lock().await = time_stamp;
@@ -163,9 +346,69 @@ impl Player {
                                 .await
                                 .unwrap_or_else(|_| unreachable!());
                         }
-                        Some(Message::AddNPC) => {
+                        Some(Message::AddNPC(npc)) => {
+                            lobby_cmd_cnl
+                                .send(LobbyCommand::AddNPC { player_id: id, npc })
+                                .await
+                                .unwrap_or_else(|_| unreachable!());
+                        }
+                        Some(Message::ListTables) => {
+                            lobby_cmd_cnl
+                                .send(LobbyCommand::ListTables { player_id: id })
+                                .await
+                                .unwrap_or_else(|_| unreachable!());
+                        }
+                        Some(Message::CreateTable(table)) => {
+                            lobby_cmd_cnl
+                                .send(LobbyCommand::CreateTable {
+                                    player_id: id,
+                                    table,
+                                })
+                                .await
+                                .unwrap_or_else(|_| unreachable!());
+                        }
+                        Some(Message::JoinPrivateTable(invite_code)) => {
                             lobby_cmd_cnl
-                                .send(LobbyCommand::AddNPC)
+                                .send(LobbyCommand::JoinPrivateTable {
+                                    player_id: id,
+                                    invite_code,
+                                })
+                                .await
+                                .unwrap_or_else(|_| unreachable!());
+                        }
+                        Some(Message::JoinTable(table_id)) => {
+                            lobby_cmd_cnl
+                                .send(LobbyCommand::JoinTable {
+                                    player_id: id,
+                                    table_id,
+                                })
+                                .await
+                                .unwrap_or_else(|_| unreachable!());
+                        }
+                        Some(Message::TableSize(seats)) => {
+                            lobby_cmd_cnl
+                                .send(LobbyCommand::TableSize {
+                                    player_id: id,
+                                    seats,
+                                })
+                                .await
+                                .unwrap_or_else(|_| unreachable!());
+                        }
+                        Some(Message::Resume(resume_token)) => {
+                            lobby_cmd_cnl
+                                .send(LobbyCommand::Resume {
+                                    player_id: id,
+                                    resume_token,
+                                })
+                                .await
+                                .unwrap_or_else(|_| unreachable!());
+                        }
+                        Some(Message::SessionLength(hands)) => {
+                            lobby_cmd_cnl
+                                .send(LobbyCommand::SessionLength {
+                                    player_id: id,
+                                    hands,
+                                })
                                 .await
                                 .unwrap_or_else(|_| unreachable!());
                         }
@@ -180,6 +423,7 @@ impl Player {
                                 .send(LobbyCommand::Disconnect { player_id: id })
                                 .await
                                 .unwrap_or_else(|_| unreachable!());
+                            break;
                         }
                     }
                     sleep(Duration::from_millis(1)).await;
@@ -188,15 +432,21 @@ impl Player {
         });
 
         let keep_alive_handle = tokio::spawn({
+            let network = nework_handle.abort_handle();
             async move {
                 loop {
                     let time_since = system_time() - *last_keep_alive.lock().await;
                     if time_since > 5000 {
+                        let id = id.load(Ordering::Relaxed);
                         println!("player with id: {} timeouted", id);
+                        network.abort();
+                        // the client only notices the connection is dead once it is closed
+                        let _ = tcp_writer.lock().await.shutdown().await;
                         lobby_cmd_cnl
                             .send(LobbyCommand::Disconnect { player_id: id })
                             .await
                             .unwrap_or_else(|_| unreachable!());
+                        break;
                     }
                     sleep(Duration::from_millis(500)).await;
                 }
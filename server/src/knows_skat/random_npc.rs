@@ -0,0 +1,71 @@
+use crate::knows_skat::KnowsSkatRules;
+use crate::knows_skat::npc::NPC;
+use async_trait::async_trait;
+use macros::message_types;
+use proto::*;
+use rand::seq::IndexedRandom;
+use std::fmt;
+
+/// The easiest bot: it never bids and plays any legal card. Everything else, like Kontra or the
+/// Skat in Schieberamsch, is answered by the `NPC` it wraps.
+pub struct RandomNPC {
+    npc: NPC,
+}
+
+impl RandomNPC {
+    pub fn named(id: u32, name: String) -> Self {
+        Self {
+            npc: NPC::named(id, name),
+        }
+    }
+
+    fn random_card(&self) -> Card {
+        let game = self.npc.game();
+        let trick = game
+            .trick
+            .iter()
+            .map(|played| played.card)
+            .collect::<Vec<_>>();
+        *legal_cards(&game.hand, &trick, game.game_type())
+            .choose(&mut rand::rng())
+            .expect("a player always holds a card when it is their turn")
+    }
+}
+
+#[async_trait]
+impl KnowsSkatRules for RandomNPC {
+    #[message_types(Trump(Declaration), PlayCard(Card), Bid(u32), TakeSkat(bool), Discard(Vec<Card>), Kontra(bool), Re(bool))]
+    async fn expect_message(&mut self) -> Message {
+        match self.npc.game().prompt {
+            Some(Message::BidTurn(_)) => Message::Bid(PASS),
+            Some(Message::YourTurn) => Message::PlayCard(self.random_card()),
+            _ => self.npc.expect_message().await,
+        }
+    }
+
+    async fn send_message(&mut self, msg: Message) {
+        self.npc.send_message(msg).await;
+    }
+
+    fn name(&self) -> String {
+        self.npc.name()
+    }
+
+    fn id(&self) -> u32 {
+        self.npc.id()
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl fmt::Debug for RandomNPC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Player")
+            .field("id", &self.npc.id())
+            .field("name", &self.npc.name())
+            .field("ip_addr", &"LOCAL (BOT, RANDOM)")
+            .finish()
+    }
+}
@@ -0,0 +1,440 @@
+use crate::knows_skat::KnowsSkatRules;
+use crate::knows_skat::npc::NPC;
+use async_trait::async_trait;
+use macros::message_types;
+use proto::*;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use solver::{Goal, OpenPosition};
+use std::fmt;
+
+/// How many deals of the unseen cards are solved for every card the bot plays.
+const SAMPLES: usize = 20;
+/// How often the bot tries to deal the unseen cards before it falls back to the simple rules.
+const DEAL_ATTEMPTS: usize = 200;
+/// Exact card points take much longer to search than a won or lost game, in Ramsch they are
+/// only searched once the bot holds this many cards.
+const EXACT_SEARCH_CARDS: usize = 4;
+
+/// A bot that bids and declares like the `NPC`, but searches for its cards: it deals the cards it
+/// hasn't seen in ways that fit everything it observed, solves each deal with all cards open and
+/// plays the card with the best average result.
+pub struct SearchNPC {
+    npc: NPC,
+    /// every card played this hand, in order
+    played: Vec<CardPlayedMessage>,
+    /// players that couldn't follow a suit, `None` stands for trump
+    voids: Vec<(u32, Option<Suit>)>,
+    /// the Skat, known once the bot declared and put it away itself
+    skat: Vec<Card>,
+}
+
+impl SearchNPC {
+    pub fn new(id: u32) -> Self {
+        Self {
+            npc: NPC::new(id),
+            played: Vec::new(),
+            voids: Vec::new(),
+            skat: Vec::new(),
+        }
+    }
+
+    pub fn named(id: u32, name: String) -> Self {
+        Self {
+            npc: NPC::named(id, name),
+            ..SearchNPC::new(id)
+        }
+    }
+
+    fn card_played(&mut self, played: &CardPlayedMessage) {
+        let game = self.npc.game();
+        let game_type = game.game_type();
+        if let Some(lead) = game.trick.first()
+            && !follows_suit(&played.card, &lead.card, game_type)
+        {
+            let void = (played.id, effective_suit(&lead.card, game_type));
+            if !self.voids.contains(&void) {
+                self.voids.push(void);
+            }
+        }
+        self.played.push(played.clone());
+    }
+
+    /// The ids of the players in playing order: forehand, middlehand and rearhand. Forehand leads
+    /// the first trick and middlehand opens the bidding. Asked when it is the bot's turn, so a
+    /// seat nobody has been seen in yet is the bot's own.
+    fn seats(&self) -> Option<[u32; 3]> {
+        let game = self.npc.game();
+        let me = self.npc.id();
+        let first_trick = |i: usize| self.played.get(i).map(|played| played.id);
+        let forehand = first_trick(0).unwrap_or(me);
+        let middlehand = first_trick(1)
+            .or_else(|| game.bids.first().map(|bid| bid.id))
+            .or_else(|| (forehand != me).then_some(me))?;
+        let rearhand = first_trick(2)
+            .or_else(|| {
+                game.bids
+                    .iter()
+                    .map(|bid| bid.id)
+                    .find(|&id| id != forehand && id != middlehand)
+            })
+            .or_else(|| (forehand != me && middlehand != me).then_some(me))?;
+        Some([forehand, middlehand, rearhand])
+    }
+
+    /// Everything the search needs to know about the hand, `None` if the bot lost track of it.
+    fn observations(&self) -> Option<Observations> {
+        let game = self.npc.game();
+        let seats = self.seats()?;
+        let seat_of = |id| seats.iter().position(|&seat| seat == id);
+        let me = seat_of(self.npc.id())?;
+        let game_type = game.game_type();
+
+        let (declarer, goal) = match game.declarer {
+            _ if game.ramsch => (me, Goal::FewestPoints),
+            Some(id) if game_type.is_null() => (seat_of(id)?, Goal::NoTrick),
+            Some(id) => (seat_of(id)?, Goal::MostPoints),
+            None => return None,
+        };
+
+        let mut hands: [Vec<Card>; 3] = Default::default();
+        hands[me] = game.hand.clone();
+        if let Some(open_hand) = &game.open_hand {
+            hands[seat_of(open_hand.id)?] = open_hand.cards.clone();
+        }
+
+        let mut sizes: [usize; 3] = [10; 3];
+        for played in &self.played {
+            sizes[seat_of(played.id)?] -= 1;
+        }
+
+        let seen = |card: &Card| {
+            hands.iter().any(|hand| hand.contains(card))
+                || self.skat.contains(card)
+                || self.played.iter().any(|played| played.card == *card)
+        };
+        let unseen = full_deck()
+            .into_iter()
+            .filter(|card| !seen(card))
+            .collect::<Vec<_>>();
+        let skat_size = 2 - self.skat.len();
+
+        let mut missing = 0;
+        for seat in 0..3 {
+            missing += sizes[seat].checked_sub(hands[seat].len())?;
+        }
+        if missing + skat_size != unseen.len() {
+            return None;
+        }
+
+        // the declarer's cards including the Skat have to be worth at least the bid
+        let bid = match game.declaration {
+            Some(declaration) if !game.ramsch && !game_type.is_null() && declarer != me => {
+                let bid = game.bids.iter().map(|bid| bid.value).max().unwrap_or(PASS);
+                let played = self
+                    .played
+                    .iter()
+                    .filter(|played| played.id == seats[declarer])
+                    .map(|played| played.card)
+                    .collect();
+                Some(BidConstraint {
+                    bid,
+                    declaration,
+                    played,
+                })
+            }
+            _ => None,
+        };
+
+        let mut taken = 0;
+        for trick in self.played.chunks_exact(3) {
+            let cards = trick.iter().map(|played| played.card).collect::<Vec<_>>();
+            if trick[trick_winner(&cards, game_type)].id == seats[declarer] {
+                taken += cards.iter().map(|card| card.rank.value()).sum::<u32>();
+            }
+        }
+
+        Some(Observations {
+            position: OpenPosition {
+                hands,
+                skat: self.skat.clone(),
+                trick: game.trick.iter().map(|played| played.card).collect(),
+                leader: match game.trick.first() {
+                    Some(lead) => seat_of(lead.id)?,
+                    None => me,
+                },
+                declarer,
+                game_type,
+                goal,
+            },
+            me,
+            preferred: self.npc.choose_card(),
+            taken,
+            sizes,
+            unseen,
+            skat: self.skat.clone(),
+            skat_size,
+            voids: self
+                .voids
+                .iter()
+                .filter_map(|&(id, suit)| Some((seat_of(id)?, suit)))
+                .collect(),
+            bid,
+        })
+    }
+}
+
+/// Limits the matadors the declarer can hold.
+#[derive(Debug, Clone)]
+struct BidConstraint {
+    bid: u32,
+    declaration: Declaration,
+    /// the declarer's cards already played
+    played: Vec<Card>,
+}
+
+impl BidConstraint {
+    /// Whether the declarer could reach the bid with these cards, even if only by playing the
+    /// opponents Schneider and Schwarz.
+    fn allows(&self, declarer_cards: &[Card]) -> bool {
+        let announcements = self.declaration.announcements;
+        let game_type = self.declaration.game_type;
+        // announcing a higher level always includes the lower ones
+        let schwarz_announced = announcements.schwarz || announcements.ouvert;
+        let schneider_announced = announcements.schneider || schwarz_announced;
+        let multiplier = matadors(declarer_cards, game_type).count
+            + [
+                true,
+                announcements.hand,
+                true,
+                schneider_announced,
+                true,
+                schwarz_announced,
+                announcements.ouvert,
+            ]
+            .into_iter()
+            .filter(|&level| level)
+            .count() as u32;
+        base_value(game_type) * multiplier >= self.bid
+    }
+}
+
+/// What the bot knows about the hand, detached from the bot so it can be searched on its own
+/// thread.
+#[derive(Debug, Clone)]
+struct Observations {
+    /// the position with the cards the bot knows about
+    position: OpenPosition,
+    me: usize,
+    /// the card the simple rules would play, it wins ties
+    preferred: Card,
+    /// the card points of the declarer's tricks so far
+    taken: u32,
+    /// how many cards every seat holds
+    sizes: [usize; 3],
+    /// the cards held by the other seats or lying in the Skat, that the bot hasn't seen
+    unseen: Vec<Card>,
+    /// the part of the Skat the bot knows
+    skat: Vec<Card>,
+    /// how many cards of the Skat the bot hasn't seen
+    skat_size: usize,
+    voids: Vec<(usize, Option<Suit>)>,
+    bid: Option<BidConstraint>,
+}
+
+impl Observations {
+    /// Deals the unseen cards so every seat gets as many cards as it holds, and nobody gets a
+    /// suit they showed they don't have. The Skat is the fourth place a card can go to. With
+    /// `check_bid` the declarer also has to be able to reach the bid.
+    fn deal(&self, rng: &mut impl Rng, check_bid: bool) -> Option<([Vec<Card>; 3], Vec<Card>)> {
+        let game_type = self.position.game_type;
+        let mut hands = self.position.hands.clone();
+        let mut skat = self.skat.clone();
+        let mut room = [0; 4];
+        for seat in 0..3 {
+            room[seat] = self.sizes[seat] - hands[seat].len();
+        }
+        room[3] = self.skat_size;
+
+        let fits = |card: &Card, place: usize| {
+            place == 3
+                || !self
+                    .voids
+                    .contains(&(place, effective_suit(card, game_type)))
+        };
+        let mut cards = self.unseen.clone();
+        cards.shuffle(rng);
+        // cards that fit in few places are dealt first
+        cards.sort_by_key(|card| (0..4).filter(|&place| fits(card, place)).count());
+
+        for card in cards {
+            let places = (0..4)
+                .filter(|&place| room[place] > 0 && fits(&card, place))
+                .collect::<Vec<_>>();
+            let total = places.iter().map(|&place| room[place]).sum::<usize>();
+            if total == 0 {
+                return None;
+            }
+            let mut pick = rng.random_range(0..total);
+            let place = places
+                .into_iter()
+                .find(|&place| {
+                    if pick < room[place] {
+                        return true;
+                    }
+                    pick -= room[place];
+                    false
+                })
+                .unwrap();
+            room[place] -= 1;
+            if place == 3 {
+                skat.push(card);
+            } else {
+                hands[place].push(card);
+            }
+        }
+
+        if let Some(bid) = self.bid.as_ref().filter(|_| check_bid) {
+            let declarer = self.position.declarer;
+            let declarer_cards = [hands[declarer].as_slice(), &skat, &bid.played].concat();
+            if !bid.allows(&declarer_cards) {
+                return None;
+            }
+        }
+        Some((hands, skat))
+    }
+
+    /// Up to `SAMPLES` deals of the unseen cards, fewer if most attempts don't fit.
+    fn sample_deals(
+        &self,
+        rng: &mut impl Rng,
+        check_bid: bool,
+    ) -> Vec<([Vec<Card>; 3], Vec<Card>)> {
+        (0..DEAL_ATTEMPTS)
+            .filter_map(|_| self.deal(rng, check_bid))
+            .take(SAMPLES)
+            .collect()
+    }
+
+    /// The card with the best result over the sampled deals, `None` if no deal fit. Suit and
+    /// Grand games are searched for whether the declarer gets the 61 points, Null games for
+    /// whether they take a trick, and Ramsch for the exact points near the end of the hand.
+    fn best_card(&self) -> Option<Card> {
+        let position = &self.position;
+        let hand = &position.hands[self.me];
+        let legal = legal_cards(hand, &position.trick, position.game_type);
+        if legal.len() == 1 {
+            return legal.first().copied();
+        }
+        if position.goal == Goal::FewestPoints && hand.len() > EXACT_SEARCH_CARDS {
+            return None;
+        }
+
+        let mut rng = rand::rng();
+        let mut deals = self.sample_deals(&mut rng, true);
+        // the declarer may have overbid, their cards don't tell anything about the bid then
+        if deals.is_empty() && self.bid.is_some() {
+            println!("search bot: no deal reaches the bid, sampling without it");
+            deals = self.sample_deals(&mut rng, false);
+        }
+        if deals.is_empty() {
+            println!("search bot: no deal fits what was played, falling back to the simple rules");
+            return None;
+        }
+
+        let mut totals = legal.iter().map(|&card| (card, 0)).collect::<Vec<_>>();
+        for (hands, skat) in deals {
+            let deal = OpenPosition {
+                hands,
+                skat,
+                ..position.clone()
+            };
+            let results = match position.goal {
+                Goal::MostPoints => solver::reaches_target(&deal, 61 - self.taken as i32)
+                    .into_iter()
+                    .map(|(card, reached)| (card, reached as u32))
+                    .collect(),
+                Goal::NoTrick => solver::reaches_target(&deal, 1)
+                    .into_iter()
+                    .map(|(card, took_trick)| (card, took_trick as u32))
+                    .collect(),
+                Goal::FewestPoints => solver::evaluate_cards(&deal),
+            };
+            for (card, value) in results {
+                if let Some((_, total)) = totals.iter_mut().find(|(c, _)| *c == card) {
+                    *total += value;
+                }
+            }
+        }
+
+        let maximizes = (self.me == position.declarer) == (position.goal == Goal::MostPoints);
+        let best = if maximizes {
+            totals.iter().map(|&(_, total)| total).max()
+        } else {
+            totals.iter().map(|&(_, total)| total).min()
+        };
+        let best_cards = totals
+            .iter()
+            .filter(|&&(_, total)| Some(total) == best)
+            .map(|&(card, _)| card)
+            .collect::<Vec<_>>();
+        if best_cards.contains(&self.preferred) {
+            Some(self.preferred)
+        } else {
+            best_cards.first().copied()
+        }
+    }
+}
+
+#[async_trait]
+impl KnowsSkatRules for SearchNPC {
+    #[message_types(Trump(Declaration), PlayCard(Card), Bid(u32), TakeSkat(bool), Discard(Vec<Card>), Kontra(bool), Re(bool))]
+    async fn expect_message(&mut self) -> Message {
+        if matches!(self.npc.game().prompt, Some(Message::YourTurn))
+            && let Some(observations) = self.observations()
+        {
+            let search = tokio::task::spawn_blocking(move || observations.best_card());
+            if let Ok(Some(card)) = search.await {
+                return Message::PlayCard(card);
+            }
+        }
+        self.npc.expect_message().await
+    }
+
+    async fn send_message(&mut self, msg: Message) {
+        match &msg {
+            Message::RoundModifier(_) | Message::Redeal => {
+                self.played.clear();
+                self.voids.clear();
+                self.skat.clear();
+            }
+            Message::CardPlayed(played) => self.card_played(played),
+            // in Schieberamsch the pushed cards can still be picked up by the others
+            Message::Discard(cards) if !self.npc.game().ramsch => self.skat = cards.clone(),
+            _ => {}
+        }
+        self.npc.send_message(msg).await;
+    }
+
+    fn name(&self) -> String {
+        self.npc.name()
+    }
+
+    fn id(&self) -> u32 {
+        self.npc.id()
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl fmt::Debug for SearchNPC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Player")
+            .field("id", &self.npc.id())
+            .field("name", &self.npc.name())
+            .field("ip_addr", &"LOCAL (BOT, SEARCH)")
+            .finish()
+    }
+}
@@ -1,27 +1,100 @@
-use crate::knows_skat::player::Player;
-use crate::knows_skat::{KnowsSkatRules, npc::NPC};
-use crate::{game::Game, pending_game::PendingGame};
+use crate::game::Game;
+use crate::knows_skat::player::{Player, ResumeHandle};
+use crate::knows_skat::{KnowsSkatRules, npc::NPC, random_npc::RandomNPC, search_npc::SearchNPC};
+use crate::pending_game::{PendingGame, generate_invite_code};
 use proto::*;
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::net::TcpStream;
 use tokio::sync::{Mutex, mpsc};
 use tokio::task::JoinHandle;
-use tokio::time::{Duration, sleep};
+use tokio::time::{Duration, Instant, sleep};
+
+/// How long a running game waits for a disconnected player, once nobody else at the table is
+/// connected.
+pub const RESUME_GRACE_PERIOD: Duration = Duration::from_secs(60);
+const RESUME_TOKEN_LENGTH: usize = 24;
+/// Names for bots added without one, the first one not taken at the table is used.
+const NPC_NAMES: [&str; 8] = [
+    "Anton",
+    "Berta",
+    "Cäsar",
+    "Dora",
+    "Emil",
+    "Friedrich",
+    "Gustav",
+    "Heinrich",
+];
 
 pub enum LobbyCommand {
-    JoinGame { player_id: u32 },
-    Disconnect { player_id: u32 },
-    Login { player_id: u32, name: String },
-    AddNPC,
+    JoinGame {
+        player_id: u32,
+    },
+    Disconnect {
+        player_id: u32,
+    },
+    /// a new connection wants to take back the seat the token was issued for
+    Resume {
+        player_id: u32,
+        resume_token: String,
+    },
+    /// the grace period of a disconnected player ran out
+    ResumeExpired {
+        player_id: u32,
+    },
+    Login {
+        player_id: u32,
+        name: String,
+    },
+    JoinTable {
+        player_id: u32,
+        table_id: u32,
+    },
+    CreateTable {
+        player_id: u32,
+        table: CreateTableMessage,
+    },
+    JoinPrivateTable {
+        player_id: u32,
+        invite_code: String,
+    },
+    ListTables {
+        player_id: u32,
+    },
+    /// adds an NPC to the table of the player
+    AddNPC {
+        player_id: u32,
+        npc: AddNpcMessage,
+    },
+    SessionLength {
+        player_id: u32,
+        hands: u32,
+    },
+    TableSize {
+        player_id: u32,
+        seats: u32,
+    },
+    /// the game task played every hand of its session
+    GameFinished {
+        player_ids: Vec<u32>,
+    },
 }
 
 pub struct Lobby {
     players: Vec<Player>,
     games: Vec<Game>,
-    pending_game: PendingGame,
+    /// the open tables, waiting for players
+    tables: Vec<PendingGame>,
+    /// one for every connected player and every seat held for a reconnect
+    resume_handles: Vec<ResumeHandle>,
+    /// players of running games whose connection dropped, and since when
+    disconnected: HashMap<u32, Instant>,
     task_handle: JoinHandle<()>,
     cmd_channel: mpsc::Sender<LobbyCommand>,
     player_count: u32,
+    table_count: u32,
 }
 
 impl Drop for Lobby {
@@ -37,10 +110,13 @@ impl Lobby {
         let this_lobby = Arc::new(Mutex::new(Self {
             players: Vec::new(),
             games: Vec::new(),
-            pending_game: PendingGame::default(),
+            tables: Vec::new(),
+            resume_handles: Vec::new(),
+            disconnected: HashMap::new(),
             task_handle: tokio::spawn(async {}),
             cmd_channel: cmd_cnl_tx,
             player_count: 0,
+            table_count: 0,
         }));
 
         let task_handle = Self::spawn_task(this_lobby.clone(), cmd_cnl_rx);
@@ -58,24 +134,64 @@ impl Lobby {
                     if let Some(cmd) = cmd_cnl_rx.recv().await {
                         match cmd {
                             LobbyCommand::JoinGame { player_id } => {
-                                let mut this_lobby = this_lobby.lock().await;
-                                let player_pos = this_lobby
-                                    .players
-                                    .iter()
-                                    .position(|p| p.id == player_id)
-                                    .clone();
-
-                                if let Some(pos) = player_pos {
-                                    let player = this_lobby.players.remove(pos);
-                                    if let Some(game) =
-                                        this_lobby.pending_game.add_player(Box::new(player)).await
-                                    {
-                                        this_lobby.games.push(game);
-                                    }
+                                this_lobby.lock().await.join_table(player_id, None).await;
+                            }
+                            LobbyCommand::JoinTable {
+                                player_id,
+                                table_id,
+                            } => {
+                                this_lobby
+                                    .lock()
+                                    .await
+                                    .join_table(player_id, Some(table_id))
+                                    .await;
+                            }
+                            LobbyCommand::CreateTable { player_id, table } => {
+                                this_lobby.lock().await.create_table(player_id, table).await;
+                            }
+                            LobbyCommand::JoinPrivateTable {
+                                player_id,
+                                invite_code,
+                            } => {
+                                this_lobby
+                                    .lock()
+                                    .await
+                                    .join_private_table(player_id, &invite_code)
+                                    .await;
+                            }
+                            LobbyCommand::ListTables { player_id } => {
+                                let mut lobby = this_lobby.lock().await;
+                                let tables = lobby.table_list();
+                                let player = lobby.players.iter_mut().find(|p| p.id == player_id);
+
+                                if let Some(player) = player {
+                                    player.send_message(Message::TableList(tables)).await;
                                 }
                             }
                             LobbyCommand::Disconnect { player_id } => {
-                                this_lobby.lock().await.remove_player(player_id).await;
+                                this_lobby.lock().await.disconnect_player(player_id).await;
+                            }
+                            LobbyCommand::Resume {
+                                player_id,
+                                resume_token,
+                            } => {
+                                this_lobby
+                                    .lock()
+                                    .await
+                                    .resume(player_id, &resume_token)
+                                    .await;
+                            }
+                            LobbyCommand::ResumeExpired { player_id } => {
+                                let mut lobby = this_lobby.lock().await;
+                                let expired = lobby
+                                    .disconnected
+                                    .get(&player_id)
+                                    .is_some_and(|since| since.elapsed() >= RESUME_GRACE_PERIOD);
+                                // the stand-in keeps playing as long as somebody is there to play with
+                                if expired && !lobby.has_connected_teammate(player_id) {
+                                    println!("player with id: {} did not come back", player_id);
+                                    lobby.remove_player(player_id).await;
+                                }
                             }
                             LobbyCommand::Login { player_id, name } => {
                                 println!(
@@ -89,18 +205,31 @@ impl Lobby {
                                     player.name = name;
                                 }
                             }
-                            LobbyCommand::AddNPC => {
-                                let mut this_lobby = this_lobby.lock().await;
-                                this_lobby.player_count += 1;
-                                let new_id = this_lobby.player_count;
-                                if let Some(game) = this_lobby
-                                    .pending_game
-                                    .add_player(Box::new(NPC::new(new_id)))
-                                    .await
-                                {
-                                    this_lobby.games.push(game);
+                            LobbyCommand::AddNPC { player_id, npc } => {
+                                this_lobby.lock().await.add_npc(player_id, npc).await;
+                            }
+                            LobbyCommand::SessionLength { player_id, hands } => {
+                                let mut lobby = this_lobby.lock().await;
+                                if let Some(table) = lobby.table_of(player_id) {
+                                    lobby.tables[table]
+                                        .set_session_length(player_id, hands)
+                                        .await;
+                                    lobby.broadcast_table_list().await;
+                                }
+                            }
+                            LobbyCommand::TableSize { player_id, seats } => {
+                                let mut lobby = this_lobby.lock().await;
+                                if let Some(table) = lobby.table_of(player_id) {
+                                    let cmd_channel = lobby.cmd_channel.clone();
+                                    let game = lobby.tables[table]
+                                        .set_table_size(player_id, seats, &cmd_channel)
+                                        .await;
+                                    lobby.table_changed(table, game).await;
                                 }
                             }
+                            LobbyCommand::GameFinished { player_ids } => {
+                                this_lobby.lock().await.finish_game(player_ids).await;
+                            }
                         }
                     }
                     sleep(Duration::from_millis(1)).await;
@@ -109,9 +238,96 @@ impl Lobby {
         })
     }
 
+    /// Lets an NPC stand in for a player of a running game, so the seat can be taken back, anybody
+    /// else is removed right away. Once nobody at the table is connected anymore, the game is
+    /// closed after the grace period.
+    async fn disconnect_player(&mut self, id: u32) {
+        if self.disconnected.contains_key(&id) {
+            return;
+        }
+
+        if !self.games.iter().any(|g| g.has_player_by_id(id)) {
+            self.remove_player(id).await;
+            return;
+        }
+
+        println!(
+            "an NPC plays for player with id: {} until they reconnect",
+            id
+        );
+        self.disconnected.insert(id, Instant::now());
+        let cmd_channel = self.cmd_channel.clone();
+        tokio::spawn(async move {
+            sleep(RESUME_GRACE_PERIOD).await;
+            let _ = cmd_channel
+                .send(LobbyCommand::ResumeExpired { player_id: id })
+                .await;
+        });
+    }
+
+    /// Whether another human of the player's game is still connected.
+    fn has_connected_teammate(&self, id: u32) -> bool {
+        let Some(game) = self.games.iter().find(|g| g.has_player_by_id(id)) else {
+            return false;
+        };
+
+        game.player_ids().iter().any(|&other| {
+            other != id
+                && !self.disconnected.contains_key(&other)
+                && self.resume_handles.iter().any(|h| h.id == other)
+        })
+    }
+
+    /// Gives the seat the token was issued for to the connection of the player with `new_id`.
+    async fn resume(&mut self, new_id: u32, resume_token: &str) {
+        let Some(pos) = self.players.iter().position(|p| p.id == new_id) else {
+            return;
+        };
+
+        let handle = self
+            .resume_handles
+            .iter()
+            .find(|h| {
+                h.id != new_id
+                    && h.resume_token == resume_token
+                    && self.games.iter().any(|g| g.has_player_by_id(h.id))
+            })
+            .cloned();
+
+        let Some(handle) = handle else {
+            let error = "there is no running game to resume with this token".to_string();
+            self.players[pos].send_message(Message::Error(error)).await;
+            self.players[pos].send_message(Message::BackToLobby).await;
+            return;
+        };
+
+        let new_player = self.players.remove(pos);
+        self.resume_handles.retain(|h| h.id != new_id);
+        if let Err(e) = handle.resume(new_player).await {
+            // the grace period of the seat keeps running
+            println!(
+                "player with id: {} failed to resume as {}: {}",
+                new_id, handle.id, e
+            );
+            return;
+        }
+        println!("player with id: {} resumed as {}", new_id, handle.id);
+        self.disconnected.remove(&handle.id);
+    }
+
     pub async fn remove_player(&mut self, id: u32) {
-        //removing from pending game
-        self.pending_game.try_remove_player(id).await;
+        self.resume_handles.retain(|h| h.id != id);
+        self.disconnected.remove(&id);
+
+        //removing from open table, closing it when only NPCs are left
+        if let Some(table) = self.table_of(id) {
+            self.tables[table].try_remove_player(id).await;
+            if !self.tables[table].has_human_player() {
+                println!("closed table {}", self.tables[table].id());
+                self.tables.remove(table);
+            }
+            self.broadcast_table_list().await;
+        }
 
         //removing from ongoing game (broadcasting closing off Game)
         let remove_game = self.games.iter().position(|g| g.has_player_by_id(id));
@@ -119,19 +335,7 @@ impl Lobby {
         if let Some(remove_game) = remove_game {
             println!("removed Game with player: {}", id);
             let game = self.games.remove(remove_game);
-
-            let mut remaining_player: Vec<Player> = game
-                .close()
-                .into_iter()
-                .filter_map(|x| x.into_any().downcast::<Player>().ok().map(|b| *b))
-                .filter(|p| p.id() != id)
-                .collect();
-
-            remaining_player
-                .broadcast_message(Message::BackToLobby)
-                .await;
-
-            self.players.extend(remaining_player);
+            self.return_to_lobby(game, Some(id)).await;
         }
 
         //removing from players list
@@ -145,21 +349,250 @@ impl Lobby {
         }
     }
 
-    pub async fn add_new_player(this: Arc<Mutex<Lobby>>, stream: TcpStream, addr: String) {
-        let cmd_channel = this.lock().await.cmd_channel.clone();
-        let id = {
-            let mut this = this.lock().await;
-            this.player_count += 1;
-            this.player_count - 1
+    fn table_of(&self, player_id: u32) -> Option<usize> {
+        self.tables
+            .iter()
+            .position(|t| t.has_player_by_id(player_id))
+    }
+
+    /// The public tables, private ones are only found by their invite code.
+    fn table_list(&self) -> Vec<TableInfo> {
+        self.tables
+            .iter()
+            .filter(|t| !t.is_private())
+            .map(|t| t.info())
+            .collect()
+    }
+
+    /// Tells everybody in the lobby which tables are open.
+    async fn broadcast_table_list(&mut self) {
+        let tables = self.table_list();
+        self.players
+            .broadcast_message(Message::TableList(tables))
+            .await;
+    }
+
+    fn open_table(&mut self, table: CreateTableMessage) -> usize {
+        let CreateTableMessage {
+            name,
+            private,
+            rules,
+        } = table;
+        self.table_count += 1;
+        println!("opened table {} with rules: {:?}", self.table_count, rules);
+        let table = if private {
+            let invite_code = loop {
+                let invite_code = generate_invite_code();
+                if !self.tables.iter().any(|t| t.admits(&invite_code)) {
+                    break invite_code;
+                }
+            };
+            PendingGame::private(self.table_count, name, rules, invite_code)
+        } else {
+            PendingGame::new(self.table_count, name, rules)
+        };
+        self.tables.push(table);
+        self.tables.len() - 1
+    }
+
+    /// Moves a player from the lobby to a public table. Without a table id the player joins the
+    /// first table with a free seat, or a new one.
+    async fn join_table(&mut self, player_id: u32, table_id: Option<u32>) {
+        let Some(pos) = self.players.iter().position(|p| p.id == player_id) else {
+            return;
+        };
+
+        let table = match table_id {
+            Some(table_id) => self
+                .tables
+                .iter()
+                .position(|t| t.id() == table_id && !t.is_private() && !t.is_full()),
+            None => Some(
+                self.tables
+                    .iter()
+                    .position(|t| !t.is_private() && !t.is_full())
+                    .unwrap_or_else(|| self.open_table(CreateTableMessage::default())),
+            ),
+        };
+
+        match table {
+            Some(table) => {
+                let player = self.players.remove(pos);
+                self.add_to_table(table, Box::new(player)).await;
+            }
+            None => {
+                let reason = format!("there is no open table with id {}", table_id.unwrap());
+                self.players[pos]
+                    .send_message(Message::JoinRejected(reason))
+                    .await;
+            }
+        }
+    }
+
+    /// Moves a player from the lobby to the private table the invite code belongs to.
+    async fn join_private_table(&mut self, player_id: u32, invite_code: &str) {
+        let Some(pos) = self.players.iter().position(|p| p.id == player_id) else {
+            return;
+        };
+
+        let table = self.tables.iter().position(|t| t.admits(invite_code));
+        let reason = match table {
+            Some(table) if !self.tables[table].is_full() => {
+                let player = self.players.remove(pos);
+                self.add_to_table(table, Box::new(player)).await;
+                return;
+            }
+            Some(_) => "the private table is already full".to_string(),
+            None => format!(
+                "there is no private table with invite code \"{}\"",
+                invite_code
+            ),
+        };
+        self.players[pos]
+            .send_message(Message::JoinRejected(reason))
+            .await;
+    }
+
+    async fn create_table(&mut self, player_id: u32, table: CreateTableMessage) {
+        let Some(pos) = self.players.iter().position(|p| p.id == player_id) else {
+            return;
+        };
+
+        let table = self.open_table(table);
+        let player = self.players.remove(pos);
+        self.add_to_table(table, Box::new(player)).await;
+    }
+
+    async fn add_npc(&mut self, player_id: u32, npc: AddNpcMessage) {
+        let Some(table) = self.table_of(player_id) else {
+            return;
+        };
+
+        let new_id = self.next_player_id();
+        let name = npc_name(npc.name, &self.tables[table].info().players);
+        let npc: Box<dyn KnowsSkatRules> = match npc.difficulty {
+            NpcDifficulty::RandomLegal => Box::new(RandomNPC::named(new_id, name)),
+            NpcDifficulty::Heuristic => Box::new(NPC::named(new_id, name)),
+            NpcDifficulty::Search => Box::new(SearchNPC::named(new_id, name)),
         };
-        let mut new_player = Player::new(stream, id, addr.to_string(), cmd_channel);
-        let msg = Message::ConfirmJoin(id);
+        self.add_to_table(table, npc).await;
+    }
+
+    /// Humans and bots count from the same number, so no two players ever share an id.
+    fn next_player_id(&mut self) -> u32 {
+        self.player_count += 1;
+        self.player_count - 1
+    }
+
+    async fn add_to_table(&mut self, table: usize, player: Box<dyn KnowsSkatRules>) {
+        let cmd_channel = self.cmd_channel.clone();
+        let game = self.tables[table].add_player(player, &cmd_channel).await;
+        self.table_changed(table, game).await;
+    }
+
+    /// Replaces a table by its game once it started, and lets the lobby know about the change.
+    async fn table_changed(&mut self, table: usize, game: Option<Game>) {
+        if let Some(game) = game {
+            self.tables.remove(table);
+            self.games.push(game);
+        }
+        self.broadcast_table_list().await;
+    }
+
+    /// Sends the players of a game whose session is over back to the lobby.
+    pub async fn finish_game(&mut self, player_ids: Vec<u32>) {
+        let finished_game = self
+            .games
+            .iter()
+            .position(|g| player_ids.iter().all(|&id| g.has_player_by_id(id)));
+
+        if let Some(finished_game) = finished_game {
+            println!("game with players: {:?} finished its session", player_ids);
+            let game = self.games.remove(finished_game);
+            self.return_to_lobby(game, None).await;
+        }
+    }
+
+    /// Closes the game and moves its human players, except the one leaving, back into the lobby.
+    async fn return_to_lobby(&mut self, game: Game, leaving: Option<u32>) {
+        let remaining_player: Vec<Player> = game
+            .close()
+            .await
+            .into_iter()
+            .filter_map(|x| x.into_any().downcast::<Player>().ok().map(|b| *b))
+            .filter(|p| Some(p.id()) != leaving)
+            .collect();
+
+        // players that never came back have nowhere to return to
+        let (gone, mut remaining_player): (Vec<Player>, Vec<Player>) = remaining_player
+            .into_iter()
+            .partition(|p| self.disconnected.contains_key(&p.id));
+        for player in gone {
+            self.resume_handles.retain(|h| h.id != player.id);
+            self.disconnected.remove(&player.id);
+        }
+
+        remaining_player
+            .broadcast_message(Message::BackToLobby)
+            .await;
+        remaining_player
+            .broadcast_message(Message::TableList(self.table_list()))
+            .await;
+
+        self.players.extend(remaining_player);
+    }
+
+    pub async fn add_new_player(this: Arc<Mutex<Lobby>>, stream: TcpStream, addr: String) {
+        // holding the lock until the player is stored, so early commands (like Login) can't miss them
+        let mut this = this.lock().await;
+        let id = this.next_player_id();
+
+        let resume_token = generate_resume_token();
+        let mut new_player = Player::new(
+            stream,
+            id,
+            resume_token.clone(),
+            addr.to_string(),
+            this.cmd_channel.clone(),
+        );
+        let msg = Message::ConfirmJoin(ConfirmJoinMessage { id, resume_token });
         new_player.send_message(msg).await;
 
-        this.lock().await.players.push(new_player);
+        this.resume_handles.push(new_player.resume_handle());
+        this.players.push(new_player);
     }
 }
 
+/// The requested name of a bot, or a free one of `NPC_NAMES`, numbered if the table already has
+/// a player of that name.
+pub fn npc_name(requested: Option<String>, taken: &[String]) -> String {
+    let name = requested
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            NPC_NAMES
+                .iter()
+                .find(|name| !taken.iter().any(|t| t == *name))
+                .map(|name| name.to_string())
+        })
+        .unwrap_or_else(|| String::from("NPC"));
+    (1..)
+        .map(|n| match n {
+            1 => name.clone(),
+            n => format!("{name} {n}"),
+        })
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap()
+}
+
+fn generate_resume_token() -> String {
+    rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(RESUME_TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
 trait VecExt<T> {
     async fn broadcast_message(&mut self, msg: Message);
 }
@@ -1,35 +1,129 @@
 use crate::game::Game;
 use crate::knows_skat::KnowsSkatRules;
+use crate::knows_skat::player::Player;
+use crate::lobby::LobbyCommand;
 use proto::*;
-use std::{fmt::Debug, mem, vec};
+use rand::Rng;
+use std::any::Any;
+use std::{fmt::Debug, mem};
+use tokio::sync::mpsc;
 
-#[derive(Default, Debug)]
+/// How many hands a session lasts unless the players agree on something else.
+pub const DEFAULT_SESSION_LENGTH: u32 = 36;
+/// Tables seat three players unless the players ask for four.
+pub const DEFAULT_TABLE_SIZE: u32 = 3;
+/// Invite codes leave out characters that are easily mixed up, like 0 and O.
+const INVITE_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const INVITE_CODE_LENGTH: usize = 6;
+
+/// A short random code to share with the players invited to a private table.
+pub fn generate_invite_code() -> String {
+    let mut rng = rand::rng();
+    (0..INVITE_CODE_LENGTH)
+        .map(|_| INVITE_CODE_CHARS[rng.random_range(0..INVITE_CODE_CHARS.len())] as char)
+        .collect()
+}
+
+/// An open table in the lobby, waiting for players.
+#[derive(Debug)]
 pub struct PendingGame {
-    player_1: Option<Box<dyn KnowsSkatRules>>,
-    player_2: Option<Box<dyn KnowsSkatRules>>,
-    player_3: Option<Box<dyn KnowsSkatRules>>,
-    player_count: u32,
+    id: u32,
+    name: Option<String>,
+    players: Vec<Box<dyn KnowsSkatRules>>,
+    /// the game starts as soon as this many players joined
+    table_size: u32,
+    session_length: u32,
+    rules: TableRules,
+    /// only private tables have one, they are hidden from the table list
+    invite_code: Option<String>,
 }
 
 impl PendingGame {
-    pub async fn add_player(&mut self, player: Box<dyn KnowsSkatRules>) -> Option<Game> {
-        println!("player: {} joined Pending Game", player.name());
-        match self.player_count {
-            0 => {
-                self.player_1 = Some(player);
-            }
-            1 => {
-                self.player_2 = Some(player);
-            }
-            _ => {
-                self.player_3 = Some(player);
-            }
+    pub fn new(id: u32, name: Option<String>, rules: TableRules) -> PendingGame {
+        PendingGame {
+            id,
+            name,
+            players: Vec::new(),
+            table_size: DEFAULT_TABLE_SIZE,
+            session_length: DEFAULT_SESSION_LENGTH,
+            rules,
+            invite_code: None,
+        }
+    }
+
+    /// A table that is left out of the table list and only joinable with the invite code.
+    pub fn private(
+        id: u32,
+        name: Option<String>,
+        rules: TableRules,
+        invite_code: String,
+    ) -> PendingGame {
+        PendingGame {
+            invite_code: Some(invite_code),
+            ..PendingGame::new(id, name, rules)
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= self.table_size as usize
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.invite_code.is_some()
+    }
+
+    /// Whether the code opens this private table, ignoring case since codes get typed in by hand.
+    pub fn admits(&self, invite_code: &str) -> bool {
+        self.invite_code
+            .as_ref()
+            .is_some_and(|code| code.eq_ignore_ascii_case(invite_code.trim()))
+    }
+
+    /// Whether anybody besides NPCs is waiting at the table.
+    pub fn has_human_player(&self) -> bool {
+        self.players
+            .iter()
+            .any(|p| (p.as_ref() as &dyn Any).is::<Player>())
+    }
+
+    /// How the table is shown in the lobby's table list.
+    pub fn info(&self) -> TableInfo {
+        TableInfo {
+            id: self.id,
+            name: self.name.clone(),
+            players: self.players.iter().map(|p| p.name()).collect(),
+            table_size: self.table_size,
+            session_length: self.session_length,
+            rules: self.rules.clone(),
+        }
+    }
+
+    pub async fn add_player(
+        &mut self,
+        mut player: Box<dyn KnowsSkatRules>,
+        lobby_cmd_cnl: &mpsc::Sender<LobbyCommand>,
+    ) -> Option<Game> {
+        player
+            .send_message(Message::TableSize(self.table_size))
+            .await;
+        player
+            .send_message(Message::SessionLength(self.session_length))
+            .await;
+        if let Some(invite_code) = &self.invite_code {
+            player
+                .send_message(Message::InviteCode(invite_code.clone()))
+                .await;
         }
-        self.player_count += 1;
+        println!("player: {} joined table {}", player.name(), self.id);
+        self.players.push(player);
 
-        let msgs = vec![&self.player_1, &self.player_2, &self.player_3]
-            .into_iter()
-            .flat_map(|p| p)
+        let msgs = self
+            .players
+            .iter()
             .map(|player| {
                 Message::PlayerJoin(PlayerJoinMessage {
                     id: player.id(),
@@ -43,64 +137,109 @@ impl PendingGame {
         }
         println!("pending game is now:\n{:#?}", self);
 
-        if self.player_count == 3 {
-            println!("pending game full: starting new game!");
-            self.broadcast_message(Message::StartGame).await;
-            Some(self.to_game())
-        } else {
-            None
-        }
+        self.try_start(lobby_cmd_cnl).await
     }
 
     pub async fn try_remove_player(&mut self, id: u32) {
-        let mut removed = false;
-        if let Some(player) = &self.player_1 {
-            if player.id() == id {
-                self.player_1 = mem::take(&mut self.player_2);
-                self.player_2 = mem::take(&mut self.player_3);
-                self.player_3 = None;
-                removed = true;
-            }
-        }
-        if let Some(player) = &self.player_2 {
-            if player.id() == id {
-                self.player_2 = mem::take(&mut self.player_3);
-                self.player_3 = None;
-                removed = true;
-            }
-        }
-        if let Some(player) = &self.player_3 {
-            if player.id() == id {
-                self.player_3 = None;
-                removed = true;
-            }
-        }
+        let player_count = self.players.len();
+        self.players.retain(|p| p.id() != id);
         self.broadcast_message(Message::PlayerLeave(id)).await;
-        if removed {
-            self.player_count -= 1;
+        if self.players.len() != player_count {
             println!("removed player with id: {} from pending game", id);
             println!("pending game is now:\n{:#?}", self);
         }
     }
 
-    pub fn to_game(&mut self) -> Game {
-        self.player_count = 0;
+    /// Lets a player of the pending game change how many hands the session lasts.
+    pub async fn set_session_length(&mut self, player_id: u32, hands: u32) {
+        if !self.has_player_by_id(player_id) {
+            return;
+        }
+
+        if hands == 0 || !hands.is_multiple_of(self.table_size) {
+            let error = format!(
+                "the session length has to be a multiple of {}, so everybody deals equally often",
+                self.table_size
+            );
+            self.send_error(player_id, error).await;
+            return;
+        }
+
+        println!("pending game: session length is now {} hands", hands);
+        self.session_length = hands;
+        self.broadcast_message(Message::SessionLength(hands)).await;
+    }
+
+    /// Lets a player of the pending game choose between a table of three and four, starting the
+    /// game if enough players are already waiting.
+    pub async fn set_table_size(
+        &mut self,
+        player_id: u32,
+        seats: u32,
+        lobby_cmd_cnl: &mpsc::Sender<LobbyCommand>,
+    ) -> Option<Game> {
+        if !self.has_player_by_id(player_id) {
+            return None;
+        }
+
+        let error = if seats != 3 && seats != 4 {
+            Some("a table has three or four seats")
+        } else if (seats as usize) < self.players.len() {
+            Some("more players are already waiting")
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            self.send_error(player_id, error.to_string()).await;
+            return None;
+        }
+
+        println!("pending game: table size is now {}", seats);
+        self.table_size = seats;
+        self.broadcast_message(Message::TableSize(seats)).await;
+
+        if !self.session_length.is_multiple_of(seats) {
+            self.session_length = self.session_length.next_multiple_of(seats);
+            self.broadcast_message(Message::SessionLength(self.session_length))
+                .await;
+        }
+
+        self.try_start(lobby_cmd_cnl).await
+    }
+
+    pub fn has_player_by_id(&self, id: u32) -> bool {
+        self.players.iter().any(|p| p.id() == id)
+    }
+
+    async fn try_start(&mut self, lobby_cmd_cnl: &mpsc::Sender<LobbyCommand>) -> Option<Game> {
+        if !self.is_full() {
+            return None;
+        }
+
+        println!("table {} full: starting new game!", self.id);
+        self.broadcast_message(Message::StartGame).await;
+        Some(self.to_game(lobby_cmd_cnl.clone()))
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_game(&mut self, lobby_cmd_cnl: mpsc::Sender<LobbyCommand>) -> Game {
         Game::new(
-            mem::take(&mut self.player_1).unwrap(),
-            mem::take(&mut self.player_2).unwrap(),
-            mem::take(&mut self.player_3).unwrap(),
+            mem::take(&mut self.players),
+            self.session_length,
+            self.rules.clone(),
+            lobby_cmd_cnl,
         )
     }
 
-    async fn broadcast_message(&mut self, msg: Message) {
-        if let Some(p) = &mut self.player_1 {
-            p.send_message(msg.clone()).await;
-        }
-        if let Some(p) = &mut self.player_2 {
-            p.send_message(msg.clone()).await;
+    async fn send_error(&mut self, player_id: u32, error: String) {
+        if let Some(player) = self.players.iter_mut().find(|p| p.id() == player_id) {
+            player.send_message(Message::Error(error)).await;
         }
-        if let Some(p) = &mut self.player_3 {
-            p.send_message(msg.clone()).await;
+    }
+
+    async fn broadcast_message(&mut self, msg: Message) {
+        for player in self.players.iter_mut() {
+            player.send_message(msg.clone()).await;
         }
     }
 }
@@ -0,0 +1,86 @@
+use crate::knows_skat::KnowsSkatRules;
+use proto::*;
+use std::ops::{Index, IndexMut};
+
+/// The players of a table, three or four of them. At a four-player table one player sits out
+/// every hand: indexing and iterating only reach the three seats taking part, numbered in table
+/// order, while broadcasts also reach the player watching.
+#[derive(Debug)]
+pub struct Seats {
+    players: Vec<Box<dyn KnowsSkatRules>>,
+    /// index into `players`
+    sitting_out: Option<usize>,
+}
+
+impl Seats {
+    pub fn new(players: Vec<Box<dyn KnowsSkatRules>>) -> Seats {
+        assert!(
+            players.len() == 3 || players.len() == 4,
+            "a table has three or four seats"
+        );
+        Seats {
+            players,
+            sitting_out: None,
+        }
+    }
+
+    /// How many players sit at the table, including the one sitting out.
+    pub fn table_size(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Every player at the table in table order, including the one sitting out.
+    pub fn table(&self) -> &[Box<dyn KnowsSkatRules>] {
+        &self.players
+    }
+
+    /// Lets the player at the given table index watch the coming hands, only at four-player tables.
+    pub fn sit_out(&mut self, table_index: usize) {
+        if self.table_size() == 4 {
+            self.sitting_out = Some(table_index);
+        }
+    }
+
+    fn table_index(&self, seat: usize) -> usize {
+        match self.sitting_out {
+            Some(sitting_out) if seat >= sitting_out => seat + 1,
+            _ => seat,
+        }
+    }
+
+    /// The three players taking part in the hand.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn KnowsSkatRules>> {
+        let sitting_out = self.sitting_out;
+        self.players
+            .iter_mut()
+            .enumerate()
+            .filter(move |(i, _)| Some(*i) != sitting_out)
+            .map(|(_, player)| player)
+    }
+
+    /// Sends the message to everybody at the table, including the player sitting out.
+    pub async fn broadcast_message(&mut self, msg: Message) {
+        for player in self.players.iter_mut() {
+            player.send_message(msg.clone()).await;
+        }
+    }
+
+    pub fn into_players(self) -> Vec<Box<dyn KnowsSkatRules>> {
+        self.players
+    }
+}
+
+impl Index<usize> for Seats {
+    type Output = Box<dyn KnowsSkatRules>;
+
+    fn index(&self, seat: usize) -> &Self::Output {
+        &self.players[self.table_index(seat)]
+    }
+}
+
+impl IndexMut<usize> for Seats {
+    fn index_mut(&mut self, seat: usize) -> &mut Self::Output {
+        let table_index = self.table_index(seat);
+        &mut self.players[table_index]
+    }
+}
@@ -0,0 +1,97 @@
+use crate::game;
+use crate::seats::Seats;
+use proto::*;
+
+/// A series of hands played by the same players, with the dealer moving on every hand.
+pub struct Session {
+    /// how many hands are played in total
+    length: u32,
+    skat_list: SkatList,
+    rounds: RoundQueue,
+}
+
+impl Session {
+    pub fn new(ids: Vec<u32>, length: u32) -> Session {
+        Session {
+            length,
+            skat_list: SkatList::new(ids),
+            rounds: RoundQueue::default(),
+        }
+    }
+
+    /// The summary sent once every hand is played.
+    pub fn summary(&self, scoring: ScoringMode) -> SessionSummaryMessage {
+        SessionSummaryMessage {
+            skat_list: self.skat_list.clone(),
+            scoring,
+            scores: self.skat_list.totals(scoring),
+        }
+    }
+
+    /// Plays every hand of the session, broadcasting the standings after each one and a summary
+    /// at the end.
+    pub async fn play(&mut self, players: &mut Seats, rules: &TableRules) {
+        let table_size = players.table_size();
+
+        for hand in 0..self.length {
+            let dealer = hand as usize % table_size;
+            let dealer_id = players.table()[dealer].id();
+            let modifier = self.rounds.next_modifier();
+            println!(
+                "hand {} of {}: {} deals",
+                hand + 1,
+                self.length,
+                players.table()[dealer].name()
+            );
+            players
+                .broadcast_message(Message::RoundModifier(modifier))
+                .await;
+
+            // the player after the dealer is forehand, at a four-player table the dealer's
+            // seat is skipped, so that is the seat the dealer would have had
+            let forehand = if table_size == 4 {
+                players.sit_out(dealer);
+                players
+                    .broadcast_message(Message::SittingOut(dealer_id))
+                    .await;
+                dealer % 3
+            } else {
+                (dealer + 1) % 3
+            };
+
+            let outcome = game::play_hand(players, forehand, modifier, rules).await;
+            if let HandOutcome::Game { result, .. } = &outcome {
+                rules.queue_rounds(
+                    &mut self.rounds,
+                    &RoundTrigger::triggered_by(result),
+                    table_size,
+                );
+            }
+
+            let entry = SkatListEntry {
+                hand: hand + 1,
+                dealer: dealer_id,
+                modifier,
+                outcome,
+            };
+            self.skat_list.push(entry.clone());
+
+            let standings = StandingsMessage {
+                ids: self.skat_list.ids.clone(),
+                scores: self.skat_list.totals(rules.scoring),
+                entry,
+                hands: self.length,
+                scoring: rules.scoring,
+            };
+            players
+                .broadcast_message(Message::Standings(standings))
+                .await;
+        }
+
+        let summary = self.summary(rules.scoring);
+        println!("session finished with scores: {:?}", summary.scores);
+        players
+            .broadcast_message(Message::SessionSummary(summary))
+            .await;
+    }
+}
@@ -1,7 +1,21 @@
 use super::*;
+use crate::bidding::{self, BidResult};
+use crate::game::{self, Deal};
+use crate::knows_skat::{
+    KnowsSkatRules, hand_evaluation, npc::NPC, player::Player, random_npc::RandomNPC,
+    search_npc::SearchNPC,
+};
+use crate::lobby::{LobbyCommand, npc_name};
+use crate::pending_game::{
+    DEFAULT_SESSION_LENGTH, DEFAULT_TABLE_SIZE, PendingGame, generate_invite_code,
+};
+use crate::seats::Seats;
+use async_trait::async_trait;
+use macros::message_types;
+use std::collections::VecDeque;
 use std::env;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::TcpStream;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::time::{Duration, sleep, timeout};
 
 const TIMEOUT_SECS: f64 = 3.0;
@@ -10,7 +24,10 @@ const TIMEOUT_SECS: f64 = 3.0;
 async fn test_default_game_1() {
     let call = default_game_1();
 
-    if let Err(_) = timeout(Duration::from_secs_f64(TIMEOUT_SECS), call).await {
+    if timeout(Duration::from_secs_f64(TIMEOUT_SECS), call)
+        .await
+        .is_err()
+    {
         panic!("timeout");
     }
 }
@@ -27,14 +44,24 @@ async fn default_game_1() {
 
     tokio::spawn(async move {
         sleep(Duration::from_secs_f64(TIMEOUT_SECS)).await;
-        assert!(false);
+        panic!("timeout");
     });
 
     let mut stream_1 = connect("127.0.0.1:1234".to_string()).await;
     stream_1
         .send_message(Message::Login("Markus Rühl".into()))
         .await;
-    assert_eq!(Message::ConfirmJoin(0), stream_1.read_message().await);
+    stream_1.send_message(Message::JoinGame).await;
+    let confirmed = confirm_join(stream_1.read_message().await);
+    assert_eq!(0, confirmed.id);
+    assert_eq!(
+        Message::TableSize(DEFAULT_TABLE_SIZE),
+        stream_1.read_message().await
+    );
+    assert_eq!(
+        Message::SessionLength(DEFAULT_SESSION_LENGTH),
+        stream_1.read_message().await
+    );
     assert_eq!(
         Message::PlayerJoin(PlayerJoinMessage {
             id: 0,
@@ -47,12 +74,24 @@ async fn default_game_1() {
     sleep(Duration::from_millis(50)).await;
     let mut stream_3 = connect("127.0.0.1:1234".to_string()).await;
     stream_2.send_message(Message::Login("Elon".into())).await;
+    stream_2.send_message(Message::JoinGame).await;
     sleep(Duration::from_millis(50)).await;
     stream_3
         .send_message(Message::Login("Mr. Beast".into()))
         .await;
-    assert_eq!(Message::ConfirmJoin(1), stream_2.read_message().await);
-    assert_eq!(Message::ConfirmJoin(2), stream_3.read_message().await);
+    stream_3.send_message(Message::JoinGame).await;
+    assert_eq!(1, confirm_join(stream_2.read_message().await).id);
+    assert_eq!(2, confirm_join(stream_3.read_message().await).id);
+    for stream in [&mut stream_2, &mut stream_3] {
+        assert_eq!(
+            Message::TableSize(DEFAULT_TABLE_SIZE),
+            stream.read_message().await
+        );
+        assert_eq!(
+            Message::SessionLength(DEFAULT_SESSION_LENGTH),
+            stream.read_message().await
+        );
+    }
     assert_eq!(
         Message::PlayerJoin(PlayerJoinMessage {
             id: 0,
@@ -97,13 +136,1267 @@ async fn default_game_1() {
         let _ = stream_2.read_message().await;
     }
 
-    //Getting Cards
     let mut streams = vec![stream_1, stream_2, stream_3];
+    for stream in &mut streams {
+        assert_eq!(Message::StartGame, stream.read_message().await);
+        assert_eq!(
+            Message::RoundModifier(RoundModifier::Normal),
+            stream.read_message().await
+        );
+    }
+
+    //Getting Cards
+    let mut hand = Vec::new();
     for _ in 0..10 {
-        for stream in &mut streams {
-            assert!(matches!(stream.read_message().await, Message::DrawCard(_)));
+        for (i, stream) in streams.iter_mut().enumerate() {
+            let msg = stream.read_message().await;
+            assert!(matches!(msg, Message::DrawCard(_)));
+            if i == 0 {
+                hand.push(msg);
+            }
         }
     }
+
+    // the first player's connection drops and comes back with the resume token
+    drop(streams.remove(0));
+    sleep(Duration::from_millis(50)).await;
+    let mut stream_1 = connect("127.0.0.1:1234".to_string()).await;
+    assert_eq!(3, confirm_join(stream_1.read_message().await).id);
+    stream_1
+        .send_message(Message::Resume(confirmed.resume_token.clone()))
+        .await;
+    assert_eq!(
+        Message::ConfirmJoin(confirmed),
+        stream_1.read_message().await
+    );
+
+    // the table is sent again, followed by a snapshot of the hand that is being played
+    let mut replay = Vec::new();
+    let snapshot = loop {
+        match stream_1.read_message().await {
+            Message::GameSnapshot(snapshot) => break snapshot,
+            msg => replay.push(msg),
+        }
+    };
+    assert!(replay.contains(&Message::StartGame));
+    assert_eq!(RoundModifier::Normal, snapshot.modifier);
+    assert_eq!(GamePhase::Bidding, snapshot.phase);
+    let hand = hand
+        .into_iter()
+        .map(|msg| match msg {
+            Message::DrawCard(card) => card,
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(hand, snapshot.hand);
+}
+
+#[tokio::test]
+async fn test_resume_twice() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let (lobby_cmd_cnl, _lobby_cmd_rx) = tokio::sync::mpsc::channel(10);
+    let mut clients = Vec::new();
+    let mut players = Vec::new();
+    for id in 0..3 {
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (stream, addr) = listener.accept().await.unwrap();
+        clients.push(BufReader::new(client));
+        players.push(Player::new(
+            stream,
+            id,
+            "token".into(),
+            addr.to_string(),
+            lobby_cmd_cnl.clone(),
+        ));
+    }
+    let mut seat = players.remove(0);
+    let handle = seat.resume_handle();
+
+    // the client comes back twice before the seat sends or reads anything
+    for new_player in players {
+        timeout(Duration::from_secs(1), handle.resume(new_player))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    timeout(
+        Duration::from_secs(1),
+        seat.send_message(Message::StartGame),
+    )
+    .await
+    .unwrap();
+
+    // the latest connection has the seat
+    let client = clients.last_mut().unwrap();
+    assert!(matches!(
+        client.read_message().await,
+        Message::ConfirmJoin(_)
+    ));
+    assert!(matches!(
+        client.read_message().await,
+        Message::GameSnapshot(_)
+    ));
+    assert_eq!(Message::StartGame, client.read_message().await);
+}
+
+#[tokio::test]
+async fn test_keep_alive_timeout_closes_the_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let (lobby_cmd_cnl, mut lobby_cmd_rx) = tokio::sync::mpsc::channel(10);
+    let client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (stream, addr) = listener.accept().await.unwrap();
+    let _player = Player::new(stream, 0, "token".into(), addr.to_string(), lobby_cmd_cnl);
+
+    // the client never sends a keep-alive, so the server gives up on it and closes the connection
+    let mut client = BufReader::new(client);
+    let mut buf = String::new();
+    let read = timeout(Duration::from_secs(10), client.read_line(&mut buf))
+        .await
+        .unwrap();
+    assert_eq!(0, read.unwrap());
+    assert!(matches!(
+        lobby_cmd_rx.recv().await,
+        Some(LobbyCommand::Disconnect { player_id: 0 })
+    ));
+}
+
+#[tokio::test]
+async fn test_stand_in() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (stream, addr) = listener.accept().await.unwrap();
+    let (lobby_cmd_cnl, mut lobby_cmd_rx) = tokio::sync::mpsc::channel(10);
+    let mut player = Player::new(stream, 0, "token".into(), addr.to_string(), lobby_cmd_cnl);
+
+    let card = Card {
+        suit: Suit::Clubs,
+        rank: Rank::Ace,
+    };
+    for msg in [
+        Message::RoundModifier(RoundModifier::Normal),
+        Message::DrawCard(card),
+        Message::YourTurn,
+    ] {
+        player.send_message(msg).await;
+    }
+    drop(client);
+    assert!(matches!(
+        lobby_cmd_rx.recv().await,
+        Some(LobbyCommand::Disconnect { player_id: 0 })
+    ));
+
+    // the NPC answers for the seat instead of waiting for the client
+    let answer = timeout(Duration::from_secs(1), player.expect_message_play_card()).await;
+    assert_eq!(card, answer.unwrap());
+}
+
+#[tokio::test]
+async fn test_stand_in_plays_a_hand() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (stream, addr) = listener.accept().await.unwrap();
+    let (lobby_cmd_cnl, mut lobby_cmd_rx) = tokio::sync::mpsc::channel(10);
+    let player = Player::new(stream, 0, "token".into(), addr.to_string(), lobby_cmd_cnl);
+
+    let mut players = Seats::new(vec![
+        Box::new(player),
+        Box::new(NPC::new(1)),
+        Box::new(NPC::new(2)),
+    ]);
+    let deal = one_sided_deal();
+    players
+        .broadcast_message(Message::RoundModifier(RoundModifier::Normal))
+        .await;
+    for (player, hand) in players.iter_mut().zip(&deal.hands) {
+        for &card in hand {
+            player.send_message(Message::DrawCard(card)).await;
+        }
+    }
+    drop(client);
+    assert!(matches!(
+        lobby_cmd_rx.recv().await,
+        Some(LobbyCommand::Disconnect { player_id: 0 })
+    ));
+
+    // the NPC declares for the dropped declarer and plays all ten tricks
+    let rules = TableRules::default();
+    let game = game::normal_game(&mut players, 0, 18, deal, &rules, false);
+    let (_, result) = timeout(Duration::from_secs(5), game).await.unwrap();
+    assert!(result.won);
+}
+
+#[tokio::test]
+async fn test_npc_plays_whole_hands() {
+    let mut players = npc_seats();
+    // the NPCs bid, or play Ramsch, on a random deal
+    let outcome = timeout(
+        Duration::from_secs(5),
+        game::play_hand(
+            &mut players,
+            0,
+            RoundModifier::Normal,
+            &TableRules::default(),
+        ),
+    )
+    .await;
+    assert!(outcome.is_ok());
+
+    let mut players = npc_seats();
+    let deal = one_sided_deal();
+    for (player, hand) in players.iter_mut().zip(&deal.hands) {
+        for &card in hand {
+            player.send_message(Message::DrawCard(card)).await;
+        }
+    }
+    // illegal cards would be asked for again forever
+    let rules = TableRules::default();
+    let game = game::normal_game(&mut players, 0, 18, deal, &rules, false);
+    let (_, result) = timeout(Duration::from_secs(5), game).await.unwrap();
+    assert!(result.won);
+}
+
+#[tokio::test]
+async fn test_npc_heuristics() {
+    use proto::{Rank::*, Suit::*};
+
+    let card = |suit, rank| Card { suit, rank };
+    let played = |id, card| Message::CardPlayed(CardPlayedMessage { id, card });
+    let setup = [
+        Message::RoundModifier(RoundModifier::Normal),
+        Message::NewBid(NewBidMessage { id: 0, value: 18 }),
+        Message::Passed(1),
+        Message::Passed(2),
+        Message::PlayNormalDuo,
+        Message::Trump(Declaration {
+            game_type: GameType::Suit(Diamonds),
+            announcements: Announcements::default(),
+        }),
+    ];
+    let hand = [
+        card(Clubs, Ace),
+        card(Clubs, Seven),
+        card(Hearts, Ten),
+        card(Hearts, Eight),
+        card(Diamonds, Nine),
+    ];
+
+    let mut npc = NPC::new(2);
+    for msg in setup.iter().cloned().chain(hand.map(Message::DrawCard)) {
+        npc.send_message(msg).await;
+    }
+
+    // the partner takes the trick, so the NPC gives it the Ten of its suit
+    npc.send_message(played(0, card(Hearts, Seven))).await;
+    npc.send_message(played(1, card(Hearts, Ace))).await;
+    npc.send_message(Message::YourTurn).await;
+    assert_eq!(card(Hearts, Ten), npc.expect_message_play_card().await);
+    npc.send_message(played(2, card(Hearts, Ten))).await;
+
+    // the declarer leads the Ten of Clubs and the NPC takes it with the Ace
+    npc.send_message(played(0, card(Clubs, Ten))).await;
+    npc.send_message(Message::YourTurn).await;
+    assert_eq!(card(Clubs, Ace), npc.expect_message_play_card().await);
+    npc.send_message(played(2, card(Clubs, Ace))).await;
+    npc.send_message(played(1, card(Clubs, Eight))).await;
+
+    // without another Ace to lead the NPC keeps its trump and leads a card without points
+    npc.send_message(Message::YourTurn).await;
+    let lead = npc.expect_message_play_card().await;
+    assert!([card(Clubs, Seven), card(Hearts, Eight)].contains(&lead));
+}
+
+#[test]
+fn test_hand_evaluation() {
+    use proto::{Rank::*, Suit::*};
+
+    let cards = |cards: &[(Suit, Rank)]| {
+        cards
+            .iter()
+            .map(|&(suit, rank)| Card { suit, rank })
+            .collect::<Vec<_>>()
+    };
+
+    // every Jack and two running suits win a Grand without the Skat
+    let grand = cards(&[
+        (Clubs, Jack),
+        (Spades, Jack),
+        (Hearts, Jack),
+        (Diamonds, Jack),
+        (Clubs, Ace),
+        (Clubs, Ten),
+        (Clubs, King),
+        (Spades, Ace),
+        (Spades, Ten),
+        (Spades, King),
+    ]);
+    let plan = hand_evaluation::best_plan(&grand).unwrap();
+    assert_eq!(GameType::Grand, plan.game_type);
+    assert!(plan.hand);
+    // with 4, game 5, Hand 6
+    assert_eq!(144, plan.value);
+
+    // no trick can be forced on these cards
+    let null = cards(&[
+        (Hearts, Seven),
+        (Hearts, Nine),
+        (Hearts, Jack),
+        (Spades, Seven),
+        (Spades, Eight),
+        (Clubs, Seven),
+        (Clubs, Nine),
+        (Diamonds, Seven),
+        (Diamonds, Eight),
+        (Diamonds, Nine),
+    ]);
+    let plan = hand_evaluation::best_plan(&null).unwrap();
+    assert_eq!(GameType::Null, plan.game_type);
+    assert!(plan.hand);
+    assert_eq!(35, plan.value);
+
+    let weak = cards(&[
+        (Hearts, King),
+        (Hearts, Queen),
+        (Hearts, Nine),
+        (Spades, King),
+        (Spades, Queen),
+        (Clubs, King),
+        (Clubs, Nine),
+        (Diamonds, King),
+        (Diamonds, Queen),
+        (Diamonds, Eight),
+    ]);
+    assert_eq!(None, hand_evaluation::best_plan(&weak));
+
+    // the lone Ten goes into the Skat, where its points count for the declarer
+    let picked_up = cards(&[
+        (Clubs, Jack),
+        (Spades, Jack),
+        (Hearts, Jack),
+        (Diamonds, Jack),
+        (Clubs, Ace),
+        (Clubs, Ten),
+        (Clubs, King),
+        (Clubs, Nine),
+        (Spades, Ace),
+        (Hearts, Ten),
+        (Diamonds, Seven),
+        (Diamonds, Eight),
+    ]);
+    let (game_type, discard) = hand_evaluation::choose_game(&picked_up, 18);
+    assert_eq!(GameType::Suit(Clubs), game_type);
+    assert_eq!(2, discard.len());
+    assert!(discard.contains(&Card {
+        suit: Hearts,
+        rank: Ten
+    }));
+    assert!(!discard.iter().any(|card| is_trump(card, game_type)));
+}
+
+#[tokio::test]
+async fn test_search_npc_plays_a_hand() {
+    let mut players = Seats::new(vec![
+        Box::new(SearchNPC::new(0)),
+        Box::new(NPC::new(1)),
+        Box::new(NPC::new(2)),
+    ]);
+    players
+        .broadcast_message(Message::RoundModifier(RoundModifier::Normal))
+        .await;
+    // the search only ever comes up with legal cards, illegal ones would be asked for again
+    let outcome = timeout(
+        Duration::from_secs(60),
+        game::play_hand(
+            &mut players,
+            0,
+            RoundModifier::Normal,
+            &TableRules::default(),
+        ),
+    )
+    .await;
+    assert!(outcome.is_ok());
+}
+
+#[tokio::test]
+async fn test_search_npc_split_jacks() {
+    use proto::{Rank::*, Suit::*};
+
+    let card = |suit, rank| Card { suit, rank };
+    let setup = [
+        Message::RoundModifier(RoundModifier::Ramsch),
+        Message::Passed(1),
+        Message::Passed(2),
+        Message::Passed(0),
+        Message::PlayRamsch,
+    ];
+    let hand = [
+        card(Spades, Seven),
+        card(Diamonds, Eight),
+        card(Hearts, King),
+        card(Diamonds, Nine),
+        card(Diamonds, King),
+        card(Diamonds, Ace),
+        card(Hearts, Nine),
+        card(Spades, Jack),
+        card(Hearts, Ten),
+        card(Diamonds, Queen),
+    ];
+    let tricks = [
+        [
+            (0, card(Spades, Seven)),
+            (1, card(Spades, Queen)),
+            (2, card(Spades, Nine)),
+        ],
+        [
+            (1, card(Spades, Ace)),
+            (2, card(Clubs, Seven)),
+            (0, card(Diamonds, Eight)),
+        ],
+        [
+            (1, card(Hearts, Queen)),
+            (2, card(Clubs, Eight)),
+            (0, card(Hearts, King)),
+        ],
+        [
+            (0, card(Diamonds, Nine)),
+            (1, card(Spades, Ten)),
+            (2, card(Clubs, Nine)),
+        ],
+        [
+            (0, card(Diamonds, King)),
+            (1, card(Hearts, Eight)),
+            (2, card(Hearts, Jack)),
+        ],
+        [
+            (2, card(Clubs, King)),
+            (0, card(Diamonds, Ace)),
+            (1, card(Clubs, Ace)),
+        ],
+        [
+            (1, card(Hearts, Seven)),
+            (2, card(Clubs, Queen)),
+            (0, card(Hearts, Nine)),
+        ],
+    ];
+
+    let mut npc = SearchNPC::new(0);
+    for msg in setup.iter().cloned().chain(hand.map(Message::DrawCard)) {
+        npc.send_message(msg).await;
+    }
+    for (id, card) in tricks.into_iter().flatten() {
+        npc.send_message(Message::CardPlayed(CardPlayedMessage { id, card }))
+            .await;
+    }
+
+    // the voids leave one deal: 1 holds King of Spades, Ace of Hearts and Eight of Spades, 2 the
+    // Jacks of Clubs and Diamonds and the Ten of Clubs. Led, the Jack of Spades gets ducked with
+    // the Jack of Diamonds, which only works if the Jacks aren't searched as equals
+    npc.send_message(Message::YourTurn).await;
+    assert_eq!(card(Hearts, Ten), npc.expect_message_play_card().await);
+}
+
+#[tokio::test]
+async fn test_random_npc_plays_a_hand() {
+    let mut players = Seats::new(
+        (0..3)
+            .map(|id| {
+                Box::new(RandomNPC::named(id, npc_name(None, &[]))) as Box<dyn KnowsSkatRules>
+            })
+            .collect(),
+    );
+    // nobody bids, so the hand is played as Schieberamsch
+    let rules = TableRules {
+        schieberamsch: true,
+        ..TableRules::default()
+    };
+    let outcome = timeout(
+        Duration::from_secs(5),
+        game::play_hand(&mut players, 0, RoundModifier::Normal, &rules),
+    )
+    .await;
+    assert!(matches!(outcome, Ok(HandOutcome::Ramsch(_))));
+}
+
+#[test]
+fn test_npc_name() {
+    assert_eq!("Anton", npc_name(None, &[]));
+    let taken = vec![String::from("Anton"), String::from("Kai")];
+    assert_eq!("Berta", npc_name(None, &taken));
+    assert_eq!("Kai 2", npc_name(Some(String::from(" Kai ")), &taken));
+    assert_eq!("Berta", npc_name(Some(String::from("  ")), &taken));
+
+    let taken = vec![String::from("Kai"), String::from("Kai 2")];
+    assert_eq!("Kai 3", npc_name(Some(String::from("Kai")), &taken));
+}
+
+/// Three NPCs with the ids 0, 1 and 2.
+fn npc_seats() -> Seats {
+    Seats::new(
+        (0..3)
+            .map(|id| Box::new(NPC::new(id)) as Box<dyn KnowsSkatRules>)
+            .collect(),
+    )
+}
+
+fn confirm_join(msg: Message) -> ConfirmJoinMessage {
+    match msg {
+        Message::ConfirmJoin(confirm_join) => confirm_join,
+        msg => panic!("expected ConfirmJoin, got {:?}", msg),
+    }
+}
+
+#[tokio::test]
+async fn test_bidding_middlehand_wins() {
+    let mut players = scripted_seats([
+        vec![Message::Bid(18), Message::Bid(PASS)],
+        vec![Message::Bid(18), Message::Bid(20)],
+        vec![Message::Bid(PASS)],
+    ]);
+
+    let result = bidding::bid(&mut players, 0).await;
+    assert_eq!(BidResult::Declarer { seat: 1, bid: 20 }, result);
+
+    let received = received_messages(players);
+    assert!(received[2].contains(&Message::NewBid(NewBidMessage { id: 1, value: 20 })));
+    assert!(received[2].contains(&Message::Passed(0)));
+}
+
+#[tokio::test]
+async fn test_bidding_rearhand_says_further() {
+    let mut players = scripted_seats([
+        vec![Message::Bid(PASS)],
+        vec![Message::Bid(18), Message::Bid(22), Message::Bid(PASS)],
+        vec![Message::Bid(22), Message::Bid(23)],
+    ]);
+
+    let result = bidding::bid(&mut players, 0).await;
+    assert_eq!(BidResult::Declarer { seat: 2, bid: 23 }, result);
+}
+
+#[tokio::test]
+async fn test_bidding_forehand_opens() {
+    let mut players = scripted_seats([
+        vec![Message::Bid(18)],
+        vec![Message::Bid(PASS)],
+        vec![Message::Bid(PASS)],
+    ]);
+
+    let result = bidding::bid(&mut players, 0).await;
+    assert_eq!(BidResult::Declarer { seat: 0, bid: 18 }, result);
+}
+
+#[tokio::test]
+async fn test_bidding_all_passed() {
+    let mut players = scripted_seats([
+        vec![Message::Bid(PASS)],
+        vec![Message::Bid(PASS)],
+        vec![Message::Bid(PASS)],
+    ]);
+
+    let result = bidding::bid(&mut players, 1).await;
+    assert_eq!(BidResult::AllPassed, result);
+}
+
+#[tokio::test]
+async fn test_bidding_rejects_illegal_bids() {
+    let mut players = scripted_seats([
+        vec![Message::Bid(20), Message::Bid(PASS)],
+        vec![Message::Bid(19), Message::Bid(18)],
+        vec![Message::Bid(PASS)],
+    ]);
+
+    let result = bidding::bid(&mut players, 0).await;
+    assert_eq!(BidResult::Declarer { seat: 1, bid: 18 }, result);
+
+    assert_eq!(vec![1, 1, 0], error_counts(&received_messages(players)));
+}
+
+#[tokio::test]
+async fn test_illegal_cards_are_rejected() {
+    use proto::{Rank::*, Suit::*};
+
+    let not_in_hand = Card {
+        suit: Clubs,
+        rank: Ace,
+    };
+    let not_following = Card {
+        suit: Hearts,
+        rank: Ten,
+    };
+    let following = Card {
+        suit: Diamonds,
+        rank: Jack,
+    };
+    let mut hand = vec![not_following, following];
+
+    let mut players = scripted_seats([
+        vec![
+            Message::PlayCard(not_in_hand),
+            Message::PlayCard(not_following),
+            Message::PlayCard(following),
+        ],
+        vec![],
+        vec![],
+    ]);
+
+    let lead = Card {
+        suit: Clubs,
+        rank: Jack,
+    };
+    let card = game::expect_legal_card(
+        players[0].as_mut(),
+        &mut hand,
+        &[lead],
+        GameType::Suit(Spades),
+    )
+    .await;
+    assert_eq!(following, card);
+    assert_eq!(vec![not_following], hand);
+
+    let received = received_messages(players).remove(0);
+    assert_eq!(
+        vec![
+            Message::YourTurn,
+            Message::IllegalCard(not_in_hand),
+            Message::YourTurn,
+            Message::IllegalCard(not_following),
+            Message::YourTurn,
+        ],
+        received
+    );
+}
+
+/// Seat 0 holds every Jack and the Clubs, so it takes every trick when playing the cards in order.
+fn one_sided_deal() -> Deal {
+    use proto::{Rank::*, Suit::*};
+
+    let cards = |cards: &[(Suit, Rank)]| {
+        cards
+            .iter()
+            .map(|&(suit, rank)| Card { suit, rank })
+            .collect::<Vec<_>>()
+    };
+    let hands = [
+        cards(&[
+            (Clubs, Jack),
+            (Spades, Jack),
+            (Hearts, Jack),
+            (Diamonds, Jack),
+            (Clubs, Ace),
+            (Clubs, Ten),
+            (Clubs, King),
+            (Clubs, Queen),
+            (Clubs, Nine),
+            (Clubs, Eight),
+        ]),
+        cards(&[
+            (Spades, Eight),
+            (Spades, Nine),
+            (Spades, Queen),
+            (Spades, King),
+            (Spades, Ten),
+            (Spades, Ace),
+            (Hearts, Queen),
+            (Hearts, King),
+            (Hearts, Ten),
+            (Hearts, Ace),
+        ]),
+        cards(&[
+            (Hearts, Seven),
+            (Hearts, Eight),
+            (Hearts, Nine),
+            (Diamonds, Seven),
+            (Diamonds, Eight),
+            (Diamonds, Nine),
+            (Diamonds, Queen),
+            (Diamonds, King),
+            (Diamonds, Ten),
+            (Diamonds, Ace),
+        ]),
+    ];
+    let skat = cards(&[(Clubs, Seven), (Spades, Seven)]);
+    Deal {
+        hands,
+        skat,
+        forehand: 0,
+    }
+}
+
+fn play_cards(cards: &[Card]) -> impl Iterator<Item = Message> {
+    cards.iter().map(|&card| Message::PlayCard(card))
+}
+
+#[tokio::test]
+async fn test_grand_schwarz() {
+    let deal = one_sided_deal();
+    let Deal { hands, skat, .. } = deal.clone();
+
+    let grand = Declaration {
+        game_type: GameType::Grand,
+        announcements: Announcements::default(),
+    };
+    let mut players = scripted_seats([
+        [Message::TakeSkat(true), Message::Discard(skat.clone())]
+            .into_iter()
+            .chain([Message::Trump(grand)])
+            .chain(play_cards(&hands[0]))
+            .collect(),
+        play_cards(&hands[1]).collect(),
+        play_cards(&hands[2]).collect(),
+    ]);
+
+    game::normal_game(&mut players, 0, 18, deal, &TableRules::default(), false).await;
+
+    for received in received_messages(players) {
+        assert!(received.contains(&Message::Trump(grand)));
+        assert!(
+            !received
+                .iter()
+                .any(|m| matches!(m, Message::IllegalCard(_)))
+        );
+
+        let Some(Message::GameWon(won)) = received.last() else {
+            panic!("the hand has to end with GameWon");
+        };
+        assert_eq!(0, won.id);
+        assert!(won.result.won);
+        assert_eq!(120, won.result.declarer_points);
+        assert_eq!(10, won.result.declarer_tricks);
+        // with 4, game 5, Schneider 6, Schwarz 7
+        assert_eq!(7, won.result.value.multiplier);
+        assert_eq!(168, won.result.value.value);
+    }
+}
+
+#[tokio::test]
+async fn test_skat_discard() {
+    let deal = one_sided_deal();
+    let Deal { hands, skat, .. } = deal.clone();
+
+    let grand = Declaration {
+        game_type: GameType::Grand,
+        announcements: Announcements::default(),
+    };
+    let grand_hand = Declaration {
+        announcements: Announcements {
+            hand: true,
+            ..Default::default()
+        },
+        ..grand
+    };
+    // the declarer keeps the Clubs Seven and puts back the Clubs Eight with the Spades Seven
+    let discard = vec![hands[0][9], skat[1]];
+    let mut declarer_hand = hands[0][..9].to_vec();
+    declarer_hand.push(skat[0]);
+
+    let mut players = scripted_seats([
+        [
+            Message::TakeSkat(true),
+            Message::Discard(vec![hands[0][9], hands[1][0]]),
+            Message::Discard(vec![skat[0]]),
+            Message::Discard(discard.clone()),
+            Message::Trump(grand_hand),
+            Message::Trump(grand),
+        ]
+        .into_iter()
+        .chain(play_cards(&declarer_hand))
+        .collect(),
+        play_cards(&hands[1]).collect(),
+        play_cards(&hands[2]).collect(),
+    ]);
+
+    let (_, result) =
+        game::normal_game(&mut players, 0, 18, deal, &TableRules::default(), false).await;
+    assert_eq!(120, result.declarer_points);
+
+    let received = received_messages(players);
+    assert!(received[0].contains(&Message::SkatTurn));
+    assert!(received[0].contains(&Message::Skat(skat)));
+    assert_eq!(
+        3,
+        received[0]
+            .iter()
+            .filter(|m| matches!(m, Message::Error(_)))
+            .count()
+    );
+    for received in received {
+        let revealed = received.len() - 2;
+        assert_eq!(Message::SkatRevealed(discard.clone()), received[revealed]);
+    }
+}
+
+#[tokio::test]
+async fn test_hand_game() {
+    let deal = one_sided_deal();
+    let Deal { hands, skat, .. } = deal.clone();
+
+    let grand_hand = Declaration {
+        game_type: GameType::Grand,
+        announcements: Announcements {
+            hand: true,
+            ..Default::default()
+        },
+    };
+    let mut players = scripted_seats([
+        [Message::TakeSkat(false), Message::Trump(grand_hand)]
+            .into_iter()
+            .chain(play_cards(&hands[0]))
+            .collect(),
+        play_cards(&hands[1]).collect(),
+        play_cards(&hands[2]).collect(),
+    ]);
+
+    let (_, result) =
+        game::normal_game(&mut players, 0, 18, deal, &TableRules::default(), false).await;
+    // the untouched Skat still counts for the declarer
+    assert_eq!(120, result.declarer_points);
+    // with 4, game 5, Hand 6, Schneider 7, Schwarz 8
+    assert_eq!(8 * 24, result.value.value);
+
+    let received = received_messages(players);
+    assert!(!received[0].contains(&Message::Skat(skat.clone())));
+    for received in received {
+        assert!(received.contains(&Message::SkatRevealed(skat.clone())));
+    }
+}
+
+#[tokio::test]
+async fn test_kontra_re() {
+    let deal = one_sided_deal();
+    let Deal { hands, skat, .. } = deal.clone();
+
+    let grand = Declaration {
+        game_type: GameType::Grand,
+        announcements: Announcements::default(),
+    };
+    let rules = TableRules {
+        kontra: true,
+        ..Default::default()
+    };
+    let mut players = scripted_seats([
+        [Message::TakeSkat(true), Message::Discard(skat.clone())]
+            .into_iter()
+            .chain([Message::Trump(grand), Message::Re(true)])
+            .chain(play_cards(&hands[0]))
+            .collect(),
+        [Message::Kontra(true)]
+            .into_iter()
+            .chain(play_cards(&hands[1]))
+            .collect(),
+        play_cards(&hands[2]).collect(),
+    ]);
+
+    game::normal_game(&mut players, 0, 18, deal, &rules, false).await;
+
+    let received = received_messages(players);
+    assert!(received[0].contains(&Message::ReTurn));
+    assert!(received[1].contains(&Message::KontraTurn));
+    assert!(!received[2].contains(&Message::KontraTurn));
+    for received in received {
+        assert!(received.contains(&Message::KontraAnnounced(1)));
+        assert!(received.contains(&Message::ReAnnounced(0)));
+
+        let Some(Message::GameWon(won)) = received.last() else {
+            panic!("the hand has to end with GameWon");
+        };
+        assert!(won.result.value.kontra && won.result.value.re);
+        assert_eq!(4 * 168, won.result.value.value);
+    }
+}
+
+#[tokio::test]
+async fn test_bock_game() {
+    let deal = one_sided_deal();
+    let Deal { hands, skat, .. } = deal.clone();
+
+    let grand = Declaration {
+        game_type: GameType::Grand,
+        announcements: Announcements::default(),
+    };
+    let mut players = scripted_seats([
+        [Message::TakeSkat(true), Message::Discard(skat.clone())]
+            .into_iter()
+            .chain([Message::Trump(grand)])
+            .chain(play_cards(&hands[0]))
+            .collect(),
+        play_cards(&hands[1]).collect(),
+        play_cards(&hands[2]).collect(),
+    ]);
+
+    let (_, result) =
+        game::normal_game(&mut players, 0, 18, deal, &TableRules::default(), true).await;
+    assert!(result.value.bock);
+    assert_eq!(2 * 168, result.value.value);
+    assert_eq!(
+        vec![RoundTrigger::HighValue],
+        RoundTrigger::triggered_by(&result)
+    );
+}
+
+#[test]
+fn test_queue_rounds() {
+    let rules = TableRules {
+        bock_triggers: vec![RoundTrigger::LostKontra, RoundTrigger::SixtySixty],
+        ramsch_triggers: vec![RoundTrigger::SixtySixty],
+        ..Default::default()
+    };
+
+    let mut rounds = RoundQueue::default();
+    rules.queue_rounds(&mut rounds, &[RoundTrigger::HighValue], 3);
+    assert!(rounds.is_empty());
+
+    rules.queue_rounds(&mut rounds, &[RoundTrigger::SixtySixty], 3);
+    let modifiers = (0..6).map(|_| rounds.next_modifier()).collect::<Vec<_>>();
+    assert_eq!(
+        [[RoundModifier::Bock; 3], [RoundModifier::Ramsch; 3]].concat(),
+        modifiers
+    );
+
+    // at a four-player table a round has four hands
+    rules.queue_rounds(
+        &mut rounds,
+        &[RoundTrigger::LostKontra, RoundTrigger::SixtySixty],
+        4,
+    );
+    let bock_hands = std::iter::from_fn(|| (!rounds.is_empty()).then(|| rounds.next_modifier()))
+        .filter(|&modifier| modifier == RoundModifier::Bock)
+        .count();
+    assert_eq!(8, bock_hands);
+}
+
+#[tokio::test]
+async fn test_schieberamsch_durchmarsch() {
+    let deal = one_sided_deal();
+    let Deal { hands, skat, .. } = deal.clone();
+    let jack_discard = vec![hands[0][0], skat[0]];
+
+    let rules = TableRules {
+        schieberamsch: true,
+        ..Default::default()
+    };
+    let mut players = scripted_seats([
+        [
+            Message::TakeSkat(true),
+            Message::Discard(jack_discard),
+            Message::Discard(skat.clone()),
+        ]
+        .into_iter()
+        .chain(play_cards(&hands[0]))
+        .collect(),
+        [Message::TakeSkat(false)]
+            .into_iter()
+            .chain(play_cards(&hands[1]))
+            .collect(),
+        [Message::TakeSkat(false)]
+            .into_iter()
+            .chain(play_cards(&hands[2]))
+            .collect(),
+    ]);
+
+    game::ramsch(&mut players, deal, &rules, false).await;
+
+    let received = received_messages(players);
+    assert!(received[0].contains(&Message::Skat(skat)));
+    assert_eq!(
+        1,
+        received[0]
+            .iter()
+            .filter(|m| matches!(m, Message::Error(_)))
+            .count()
+    );
+    for received in received {
+        assert!(received.contains(&Message::PlayRamsch));
+        assert!(received.contains(&Message::SkatPushed(2)));
+
+        let Some(Message::RamschResult(ramsch)) = received.last() else {
+            panic!("the hand has to end with RamschResult");
+        };
+        assert_eq!([0, 1, 2], ramsch.ids);
+        assert_eq!(2, ramsch.result.pushes);
+        assert_eq!(Some(0), ramsch.result.durchmarsch);
+        assert_eq!([120, 0, 0], ramsch.result.scores);
+    }
+}
+
+#[tokio::test]
+async fn test_session_length() {
+    let (lobby_cmd_cnl, _lobby_cmd_rx) = tokio::sync::mpsc::channel(10);
+    let mut pending_game = PendingGame::new(1, None, TableRules::default());
+    let [player_1, player_2, player_3] = scripted_players(Default::default());
+
+    assert!(
+        pending_game
+            .add_player(player_1, &lobby_cmd_cnl)
+            .await
+            .is_none()
+    );
+    assert!(
+        pending_game
+            .add_player(player_2, &lobby_cmd_cnl)
+            .await
+            .is_none()
+    );
+
+    // only players of the pending game may change it, and only to a multiple of 3
+    pending_game.set_session_length(7, 12).await;
+    pending_game.set_session_length(1, 10).await;
+    pending_game.set_session_length(0, 12).await;
+
+    let game = pending_game
+        .add_player(player_3, &lobby_cmd_cnl)
+        .await
+        .expect("the third player starts the game");
+    // closing right away, before the game task gets to run
+    let received = received_messages(Seats::new(game.close().await));
+
+    // everybody is told the current length when joining, and about every change
+    assert_eq!(
+        Message::SessionLength(DEFAULT_SESSION_LENGTH),
+        received[0][1]
+    );
+    assert_eq!(
+        Message::SessionLength(DEFAULT_SESSION_LENGTH),
+        received[1][1]
+    );
+    assert_eq!(Message::SessionLength(12), received[2][1]);
+    for received in &received {
+        assert!(received.contains(&Message::SessionLength(12)));
+        assert!(!received.contains(&Message::SessionLength(10)));
+    }
+    assert_eq!(vec![0, 1, 0], error_counts(&received));
+}
+
+#[tokio::test]
+async fn test_table_info() {
+    let (lobby_cmd_cnl, _lobby_cmd_rx) = tokio::sync::mpsc::channel(10);
+    let rules = TableRules {
+        kontra: true,
+        bock_triggers: vec![RoundTrigger::LostKontra],
+        null_matadors: true,
+        ..Default::default()
+    };
+    let mut pending_game = PendingGame::new(7, Some("Stammtisch".into()), rules.clone());
+    let [player_1, player_2] = scripted_players(Default::default());
+    for player in [player_1, player_2] {
+        assert!(
+            pending_game
+                .add_player(player, &lobby_cmd_cnl)
+                .await
+                .is_none()
+        );
+    }
+
+    assert_eq!(
+        TableInfo {
+            id: 7,
+            name: Some("Stammtisch".into()),
+            players: vec!["scripted 0".into(), "scripted 1".into()],
+            table_size: DEFAULT_TABLE_SIZE,
+            session_length: DEFAULT_SESSION_LENGTH,
+            rules,
+        },
+        pending_game.info()
+    );
+    assert!(!pending_game.is_full());
+    // scripted players are no humans, so the lobby would close this table
+    assert!(!pending_game.has_human_player());
+}
+
+#[tokio::test]
+async fn test_private_table() {
+    let (lobby_cmd_cnl, _lobby_cmd_rx) = tokio::sync::mpsc::channel(10);
+    let invite_code = generate_invite_code();
+    assert_eq!(6, invite_code.len());
+    assert!(!invite_code.contains(['0', 'O', '1', 'I']));
+
+    let mut pending_game =
+        PendingGame::private(3, None, TableRules::default(), invite_code.clone());
+    assert!(pending_game.is_private());
+    assert!(pending_game.admits(&invite_code));
+    assert!(pending_game.admits(&format!(" {} ", invite_code.to_lowercase())));
+    assert!(!pending_game.admits("WRONG1"));
+    assert!(!PendingGame::new(4, None, TableRules::default()).admits(&invite_code));
+
+    let [player_1, player_2, player_3] = scripted_players(Default::default());
+    for player in [player_1, player_2] {
+        assert!(
+            pending_game
+                .add_player(player, &lobby_cmd_cnl)
+                .await
+                .is_none()
+        );
+    }
+    let game = pending_game.add_player(player_3, &lobby_cmd_cnl).await;
+
+    let received = game
+        .unwrap()
+        .close()
+        .await
+        .into_iter()
+        .map(|p| p.into_any().downcast::<ScriptedPlayer>().unwrap().received)
+        .collect::<Vec<_>>();
+    for received in &received {
+        assert!(received.contains(&Message::InviteCode(invite_code.clone())));
+    }
+}
+
+#[tokio::test]
+async fn test_four_player_table() {
+    let (lobby_cmd_cnl, _lobby_cmd_rx) = tokio::sync::mpsc::channel(10);
+    let mut pending_game = PendingGame::new(1, None, TableRules::default());
+    let [player_1, player_2, player_3, player_4] = scripted_players(Default::default());
+
+    assert!(
+        pending_game
+            .add_player(player_1, &lobby_cmd_cnl)
+            .await
+            .is_none()
+    );
+    pending_game.set_session_length(0, 9).await;
+    // 9 hands can't be split evenly between four dealers
+    assert!(
+        pending_game
+            .set_table_size(0, 4, &lobby_cmd_cnl)
+            .await
+            .is_none()
+    );
+    for player in [player_2, player_3] {
+        assert!(
+            pending_game
+                .add_player(player, &lobby_cmd_cnl)
+                .await
+                .is_none()
+        );
+    }
+    assert!(
+        pending_game
+            .set_table_size(1, 5, &lobby_cmd_cnl)
+            .await
+            .is_none()
+    );
+
+    let game = pending_game
+        .add_player(player_4, &lobby_cmd_cnl)
+        .await
+        .expect("the fourth player starts the game");
+    assert!(game.has_player_by_id(3));
+
+    let received = received_messages(Seats::new(game.close().await));
+    assert_eq!(4, received.len());
+    assert!(received[0].contains(&Message::TableSize(4)));
+    assert!(received[0].contains(&Message::SessionLength(12)));
+    assert_eq!(Message::TableSize(4), received[3][0]);
+    assert_eq!(Message::SessionLength(12), received[3][1]);
+    assert_eq!(vec![0, 1, 0, 0], error_counts(&received));
+}
+
+#[tokio::test]
+async fn test_dealer_sits_out() {
+    let mut players = scripted_seats([
+        vec![Message::Bid(18)],
+        vec![Message::Bid(PASS)],
+        vec![],
+        vec![Message::Bid(PASS)],
+    ]);
+    // the dealer at table index 2 watches, so the player after them is forehand
+    players.sit_out(2);
+    assert_eq!(3, players[2].id());
+    assert_eq!(3, players.iter_mut().count());
+
+    let result = bidding::bid(&mut players, 2).await;
+    assert_eq!(BidResult::Declarer { seat: 0, bid: 18 }, result);
+
+    let received = received_messages(players);
+    assert!(received[3].contains(&Message::Hear));
+    assert!(received[0].contains(&Message::Say));
+    assert!(received[1].contains(&Message::SayFurther));
+    // the dealer follows the bidding without taking part
+    assert_eq!(
+        vec![
+            Message::NewBid(NewBidMessage { id: 0, value: 18 }),
+            Message::Passed(3),
+            Message::Passed(1),
+        ],
+        received[2]
+    );
+}
+
+/// A seat that answers with a fixed list of messages and records everything it is sent.
+#[derive(Debug)]
+struct ScriptedPlayer {
+    id: u32,
+    answers: VecDeque<Message>,
+    received: Vec<Message>,
+}
+
+#[async_trait]
+impl KnowsSkatRules for ScriptedPlayer {
+    #[message_types(Trump(Declaration), PlayCard(Card), Bid(u32), TakeSkat(bool), Discard(Vec<Card>), Kontra(bool), Re(bool))]
+    async fn expect_message(&mut self) -> Message {
+        self.answers
+            .pop_front()
+            .unwrap_or_else(|| panic!("player: {} ran out of answers", self.id))
+    }
+
+    async fn send_message(&mut self, msg: Message) {
+        self.received.push(msg);
+    }
+
+    fn name(&self) -> String {
+        format!("scripted {}", self.id)
+    }
+
+    fn id(&self) -> u32 {
+        self.id
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+/// Scripted players with the ids 0, 1, ...
+fn scripted_players<const N: usize>(answers: [Vec<Message>; N]) -> [Box<dyn KnowsSkatRules>; N] {
+    let mut id = 0;
+    answers.map(|answers| {
+        id += 1;
+        Box::new(ScriptedPlayer {
+            id: id - 1,
+            answers: answers.into(),
+            received: Vec::new(),
+        }) as Box<dyn KnowsSkatRules>
+    })
+}
+
+fn scripted_seats<const N: usize>(answers: [Vec<Message>; N]) -> Seats {
+    Seats::new(scripted_players(answers).into())
+}
+
+fn received_messages(players: Seats) -> Vec<Vec<Message>> {
+    players
+        .into_players()
+        .into_iter()
+        .map(|p| p.into_any().downcast::<ScriptedPlayer>().unwrap().received)
+        .collect()
+}
+
+fn error_counts(received: &[Vec<Message>]) -> Vec<usize> {
+    received
+        .iter()
+        .map(|received| {
+            received
+                .iter()
+                .filter(|m| matches!(m, Message::Error(_)))
+                .count()
+        })
+        .collect()
 }
 
 async fn connect(ip: String) -> BufReader<TcpStream> {
@@ -118,14 +1411,19 @@ trait BufReaderExt {
 impl BufReaderExt for BufReader<TcpStream> {
     async fn send_message(&mut self, msg: Message) {
         let serialized = serde_json::to_string(&msg).unwrap();
-        self.write_all(&serialized.as_bytes()).await.unwrap();
+        self.write_all(serialized.as_bytes()).await.unwrap();
         self.write_all("\n".as_bytes()).await.unwrap();
     }
 
+    /// Reads the next message, skipping the table lists the lobby sends whenever a table changes.
     async fn read_message(&mut self) -> Message {
-        let mut buf = String::new();
-        let _ = self.read_line(&mut buf).await.unwrap();
-        let msg: Message = serde_json::from_str(&buf).unwrap();
-        msg
+        loop {
+            let mut buf = String::new();
+            let _ = self.read_line(&mut buf).await.unwrap();
+            let msg: Message = serde_json::from_str(&buf).unwrap();
+            if !matches!(msg, Message::TableList(_)) {
+                return msg;
+            }
+        }
     }
 }
@@ -0,0 +1,25 @@
+use proto::{Card, Rank, Suit};
+
+/// A set of cards, one bit per card.
+pub type Cards = u32;
+
+pub fn index(card: &Card) -> usize {
+    let suit = Suit::ALL.iter().position(|&s| s == card.suit).unwrap();
+    let rank = Rank::ALL.iter().position(|&r| r == card.rank).unwrap();
+    suit * 8 + rank
+}
+
+pub fn bit(card: &Card) -> Cards {
+    1 << index(card)
+}
+
+pub fn to_cards(cards: &[Card]) -> Cards {
+    cards.iter().map(bit).fold(0, |set, bit| set | bit)
+}
+
+pub fn card_at(index: usize) -> Card {
+    Card {
+        suit: Suit::ALL[index / 8],
+        rank: Rank::ALL[index % 8],
+    }
+}
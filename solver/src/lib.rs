@@ -0,0 +1,88 @@
+//! Double-dummy solver for Skat: plays out a hand where every card is known, with every player
+//! choosing their best card.
+
+use proto::{Card, GameType};
+
+mod cards;
+mod search;
+#[cfg(test)]
+mod tests;
+
+use cards::card_at;
+use search::Search;
+
+/// What the declarer plays for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// as many card points as possible
+    MostPoints,
+    /// as few card points as possible, like everybody in Ramsch
+    FewestPoints,
+    /// not a single trick, as in Null
+    NoTrick,
+}
+
+/// A hand where every card is known, the seats are numbered in playing order.
+#[derive(Debug, Clone)]
+pub struct OpenPosition {
+    pub hands: [Vec<Card>; 3],
+    /// belongs to the declarer in suit and Grand games, and to whoever takes the last trick in
+    /// Ramsch
+    pub skat: Vec<Card>,
+    /// the cards of the unfinished trick, at most two
+    pub trick: Vec<Card>,
+    /// the seat that led the unfinished trick, or leads the next one
+    pub leader: usize,
+    pub declarer: usize,
+    pub game_type: GameType,
+    pub goal: Goal,
+}
+
+/// The result of a position with perfect play. Tricks finished before the position don't count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// the card points the declarer gets out of the remaining cards and the Skat
+    Points(u32),
+    /// whether the declarer gets through the Null game without taking a trick
+    Null { won: bool },
+}
+
+/// The result for the declarer if everybody plays perfectly from here.
+pub fn solve(position: &OpenPosition) -> Outcome {
+    let mut search = Search::new(position);
+    match position.goal {
+        Goal::NoTrick => Outcome::Null {
+            won: search.root(position, 0, 1) < 1,
+        },
+        _ => Outcome::Points(search.root(position, i32::MIN, i32::MAX) as u32),
+    }
+}
+
+/// The result of each card the seat to play can choose, assuming everybody plays perfectly
+/// afterwards. The result is what the declarer gets out of the remaining cards: card points, or
+/// in Null 1 if they take a trick and 0 if they don't.
+pub fn evaluate_cards(position: &OpenPosition) -> Vec<(Card, u32)> {
+    let mut search = Search::new(position);
+    search
+        .root_cards(position)
+        .into_iter()
+        .map(|card| {
+            let value = search.after(position, card, i32::MIN, i32::MAX);
+            (card_at(card), value as u32)
+        })
+        .collect()
+}
+
+/// Whether the declarer gets at least `target` out of the remaining cards after each card the
+/// seat to play can choose. Much faster than the exact values of `evaluate_cards`.
+pub fn reaches_target(position: &OpenPosition, target: i32) -> Vec<(Card, bool)> {
+    let mut search = Search::new(position);
+    search
+        .root_cards(position)
+        .into_iter()
+        .map(|card| {
+            let value = search.after(position, card, target - 1, target);
+            (card_at(card), value >= target)
+        })
+        .collect()
+}
@@ -0,0 +1,292 @@
+use crate::cards::{Cards, card_at, index, to_cards};
+use crate::{Goal, OpenPosition};
+use proto::*;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// A cheap hasher for the positions in the table, the keys are already well mixed card sets.
+#[derive(Default)]
+struct PositionHasher(u64);
+
+impl Hasher for PositionHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(byte as u64);
+        }
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.write_u64(n as u64);
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+}
+
+/// Lower and upper bound of the value of a position at the start of a trick.
+type Bounds = (i32, i32);
+
+/// The unfinished trick as card indices.
+type Trick = [usize; 3];
+
+pub struct Search {
+    declarer: usize,
+    goal: Goal,
+    /// the points of the Skat if the declarer ends up with them
+    skat_points: i32,
+    trumps: Cards,
+    strength: [u32; 32],
+    /// the cards that follow each card led, including the card itself
+    following: [Cards; 32],
+    /// every card index grouped by the suit it follows, strongest first
+    order: [usize; 32],
+    points: [i32; 32],
+    known: HashMap<([Cards; 3], usize), Bounds, BuildHasherDefault<PositionHasher>>,
+}
+
+impl Search {
+    pub fn new(position: &OpenPosition) -> Self {
+        let game_type = position.game_type;
+        let deck = full_deck();
+        let following = std::array::from_fn(|i| {
+            let lead = card_at(i);
+            to_cards(
+                &deck
+                    .iter()
+                    .copied()
+                    .filter(|card| follows_suit(card, &lead, game_type))
+                    .collect::<Vec<_>>(),
+            )
+        });
+        let mut order: [usize; 32] = std::array::from_fn(|i| i);
+        order.sort_by_key(|&i| {
+            let card = card_at(i);
+            let trump = is_trump(&card, game_type);
+            // the trumps form one suit, the Jacks rank by their suit above the others
+            let suit = if trump { 0 } else { card.suit as u8 + 1 };
+            std::cmp::Reverse((trump, suit, card_strength(&card, game_type)))
+        });
+
+        Self {
+            declarer: position.declarer,
+            goal: position.goal,
+            skat_points: position
+                .skat
+                .iter()
+                .map(|card| card.rank.value() as i32)
+                .sum(),
+            trumps: to_cards(
+                &deck
+                    .iter()
+                    .copied()
+                    .filter(|card| is_trump(card, game_type))
+                    .collect::<Vec<_>>(),
+            ),
+            strength: std::array::from_fn(|i| card_strength(&card_at(i), game_type)),
+            following,
+            order,
+            points: std::array::from_fn(|i| card_at(i).rank.value() as i32),
+            known: HashMap::default(),
+        }
+    }
+
+    /// The declarer's value of the position.
+    pub fn root(&mut self, position: &OpenPosition, alpha: i32, beta: i32) -> i32 {
+        let (hands, trick, len) = self.start(position);
+        self.search(hands, trick, len, position.leader, alpha, beta)
+    }
+
+    /// The cards the seat to play can choose, the strongest of each suit first.
+    pub fn root_cards(&self, position: &OpenPosition) -> Vec<usize> {
+        let (hands, trick, len) = self.start(position);
+        let seat = (position.leader + len) % 3;
+        let playable = self.playable(hands[seat], (len > 0).then_some(trick[0]));
+        self.order
+            .iter()
+            .copied()
+            .filter(|&i| playable & (1 << i) != 0)
+            .collect()
+    }
+
+    /// The declarer's value after the seat to play chose `card`.
+    pub fn after(&mut self, position: &OpenPosition, card: usize, alpha: i32, beta: i32) -> i32 {
+        let (mut hands, mut trick, len) = self.start(position);
+        let seat = (position.leader + len) % 3;
+        hands[seat] &= !(1 << card);
+        trick[len] = card;
+        self.play(hands, trick, len + 1, position.leader, alpha, beta)
+    }
+
+    /// The hands and the unfinished trick of the position as card sets and indices.
+    fn start(&self, position: &OpenPosition) -> ([Cards; 3], Trick, usize) {
+        assert!(
+            position.trick.len() < 3,
+            "a finished trick is not a position"
+        );
+        let hands = position.hands.clone().map(|hand| to_cards(&hand));
+        let mut trick = [0; 3];
+        for (i, played) in position.trick.iter().enumerate() {
+            trick[i] = index(played);
+        }
+        (hands, trick, position.trick.len())
+    }
+
+    /// Same as `proto::trick_winner`, on card indices.
+    fn trick_winner(&self, trick: Trick) -> usize {
+        let power = |i: usize| {
+            let strength = self.strength[i];
+            if self.trumps & (1 << i) != 0 {
+                200 + strength
+            } else if self.following[trick[0]] & (1 << i) != 0 {
+                100 + strength
+            } else {
+                0
+            }
+        };
+        (0..3).max_by_key(|&i| power(trick[i])).unwrap()
+    }
+
+    fn playable(&self, hand: Cards, lead: Option<usize>) -> Cards {
+        let following = lead.map_or(hand, |lead| hand & self.following[lead]);
+        if following == 0 { hand } else { following }
+    }
+
+    fn maximizes(&self, seat: usize) -> bool {
+        (seat == self.declarer) == (self.goal == Goal::MostPoints)
+    }
+
+    /// Finishes the trick if it is complete and searches on, returns the declarer's value of the
+    /// cards still to be played including the trick.
+    fn play(
+        &mut self,
+        hands: [Cards; 3],
+        trick: Trick,
+        len: usize,
+        leader: usize,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if len < 3 {
+            return self.search(hands, trick, len, leader, alpha, beta);
+        }
+
+        let winner = (leader + self.trick_winner(trick)) % 3;
+        let last = hands.iter().all(|&hand| hand == 0);
+        let skat = match self.goal {
+            Goal::MostPoints if last => self.skat_points,
+            // in Ramsch the last trick takes the Skat
+            Goal::FewestPoints if last && winner == self.declarer => self.skat_points,
+            _ => 0,
+        };
+        let value = skat
+            + match self.goal {
+                _ if winner != self.declarer => 0,
+                // the Null game is lost, nothing else matters
+                Goal::NoTrick => return 1,
+                _ => trick.iter().map(|&i| self.points[i]).sum(),
+            };
+        let rest = self.search(
+            hands,
+            [0; 3],
+            0,
+            winner,
+            alpha.saturating_sub(value),
+            beta.saturating_sub(value),
+        );
+        value + rest
+    }
+
+    /// Alpha-beta search of the position, positions at the start of a trick are remembered.
+    fn search(
+        &mut self,
+        mut hands: [Cards; 3],
+        mut trick: Trick,
+        len: usize,
+        leader: usize,
+        mut alpha: i32,
+        mut beta: i32,
+    ) -> i32 {
+        let start = len == 0;
+        if start && hands[leader] == 0 {
+            return 0;
+        }
+
+        let (alpha_start, beta_start) = (alpha, beta);
+        if start && let Some(&(lower, upper)) = self.known.get(&(hands, leader)) {
+            if lower >= beta || lower == upper {
+                return lower;
+            }
+            if upper <= alpha {
+                return upper;
+            }
+        }
+
+        let seat = (leader + len) % 3;
+        let maximizes = self.maximizes(seat);
+        let playable = self.playable(hands[seat], (len > 0).then_some(trick[0]));
+        // cards still in the game, a card between two of the same hand makes them different
+        let alive =
+            hands[0] | hands[1] | hands[2] | trick[..len].iter().fold(0, |set, &i| set | 1 << i);
+
+        let mut best = if maximizes { i32::MIN } else { i32::MAX };
+        let mut previous: Option<usize> = None;
+        for index in self.order {
+            if alive & (1 << index) == 0 {
+                continue;
+            }
+            let last = previous.replace(index);
+            if playable & (1 << index) == 0 {
+                continue;
+            }
+            // the next higher card in the game is ours and worth the same, so this one plays the
+            // same way
+            if let Some(last) = last
+                && playable & (1 << last) != 0
+                && self.points[last] == self.points[index]
+                && self.following[last] & (1 << index) != 0
+            {
+                continue;
+            }
+
+            hands[seat] &= !(1 << index);
+            trick[len] = index;
+            let value = self.play(hands, trick, len + 1, leader, alpha, beta);
+            hands[seat] |= 1 << index;
+
+            if maximizes {
+                best = best.max(value);
+                alpha = alpha.max(value);
+            } else {
+                best = best.min(value);
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if start {
+            let bounds = self
+                .known
+                .entry((hands, leader))
+                .or_insert((0, 120 + self.skat_points));
+            if best <= alpha_start {
+                bounds.1 = bounds.1.min(best);
+            } else if best >= beta_start {
+                bounds.0 = bounds.0.max(best);
+            } else {
+                *bounds = (best, best);
+            }
+        }
+        best
+    }
+}
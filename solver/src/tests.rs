@@ -0,0 +1,267 @@
+use crate::*;
+use proto::{Rank::*, Suit::*, *};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+fn card(suit: Suit, rank: Rank) -> Card {
+    Card { suit, rank }
+}
+
+/// Plays out every line with the rules of proto, without any of the solver's shortcuts.
+fn brute_force(position: &OpenPosition) -> u32 {
+    let OpenPosition {
+        hands,
+        trick,
+        leader,
+        declarer,
+        game_type,
+        goal,
+        ..
+    } = position.clone();
+    if trick.len() == 3 {
+        let winner = (leader + trick_winner(&trick, game_type)) % 3;
+        let last = hands.iter().all(|hand| hand.is_empty());
+        let mut value = 0;
+        if winner == declarer {
+            if goal == Goal::NoTrick {
+                return 1;
+            }
+            value += trick.iter().map(|card| card.rank.value()).sum::<u32>();
+        }
+        let skat_owner = match goal {
+            Goal::MostPoints => Some(declarer),
+            Goal::FewestPoints => Some(winner),
+            Goal::NoTrick => None,
+        };
+        if last && skat_owner == Some(declarer) {
+            value += position
+                .skat
+                .iter()
+                .map(|card| card.rank.value())
+                .sum::<u32>();
+        }
+        if last {
+            return value;
+        }
+        return value
+            + brute_force(&OpenPosition {
+                trick: Vec::new(),
+                leader: winner,
+                ..position.clone()
+            });
+    }
+
+    let seat = (leader + trick.len()) % 3;
+    let values = legal_cards(&hands[seat], &trick, game_type)
+        .into_iter()
+        .map(|card| {
+            let mut next = position.clone();
+            next.hands[seat].retain(|&c| c != card);
+            next.trick.push(card);
+            brute_force(&next)
+        });
+    if (seat == declarer) == (goal == Goal::MostPoints) {
+        values.max().unwrap()
+    } else {
+        values.min().unwrap()
+    }
+}
+
+#[test]
+fn test_evaluate_cards() {
+    // the declarer can't follow the Ace of Spades: ruffing it gives away the Ace of Diamonds
+    // later, throwing the Ten gets Ace and King back with the last trump
+    let position = OpenPosition {
+        hands: [
+            vec![card(Diamonds, Ace)],
+            vec![card(Diamonds, King)],
+            vec![card(Hearts, Seven), card(Diamonds, Ten)],
+        ],
+        skat: Vec::new(),
+        trick: vec![card(Spades, Ace), card(Spades, Seven)],
+        leader: 0,
+        declarer: 2,
+        game_type: GameType::Suit(Hearts),
+        goal: Goal::MostPoints,
+    };
+    assert_eq!(
+        vec![(card(Hearts, Seven), 11), (card(Diamonds, Ten), 15)],
+        evaluate_cards(&position)
+    );
+    assert_eq!(
+        vec![(card(Hearts, Seven), false), (card(Diamonds, Ten), true)],
+        reaches_target(&position, 15)
+    );
+    assert_eq!(Outcome::Points(15), solve(&position));
+
+    // leading the Seven lets the defenders take the tricks in Null
+    let position = OpenPosition {
+        hands: [
+            vec![card(Hearts, Seven), card(Hearts, Ace)],
+            vec![card(Hearts, Eight), card(Spades, Seven)],
+            vec![card(Hearts, Nine), card(Spades, Eight)],
+        ],
+        skat: Vec::new(),
+        trick: Vec::new(),
+        leader: 0,
+        declarer: 0,
+        game_type: GameType::Null,
+        goal: Goal::NoTrick,
+    };
+    assert_eq!(
+        vec![(card(Hearts, Ace), 1), (card(Hearts, Seven), 0)],
+        evaluate_cards(&position)
+    );
+    assert_eq!(Outcome::Null { won: true }, solve(&position));
+}
+
+#[test]
+fn test_solve_null() {
+    // the Nine of Hearts can't get under the others
+    let position = OpenPosition {
+        hands: [
+            vec![card(Hearts, Nine)],
+            vec![card(Hearts, Seven)],
+            vec![card(Hearts, Eight)],
+        ],
+        skat: vec![card(Clubs, Seven), card(Clubs, Eight)],
+        trick: Vec::new(),
+        leader: 1,
+        declarer: 0,
+        game_type: GameType::NullHand,
+        goal: Goal::NoTrick,
+    };
+    assert_eq!(Outcome::Null { won: false }, solve(&position));
+
+    let position = OpenPosition {
+        leader: 0,
+        hands: [
+            vec![card(Hearts, Seven)],
+            vec![card(Hearts, Eight)],
+            vec![card(Hearts, Nine)],
+        ],
+        ..position
+    };
+    assert_eq!(Outcome::Null { won: true }, solve(&position));
+}
+
+#[test]
+fn test_skat_points() {
+    let hands = [
+        vec![card(Clubs, Jack)],
+        vec![card(Hearts, Ace)],
+        vec![card(Hearts, Seven)],
+    ];
+    let position = OpenPosition {
+        hands,
+        skat: vec![card(Spades, Ten), card(Diamonds, King)],
+        trick: Vec::new(),
+        leader: 1,
+        declarer: 0,
+        game_type: GameType::Grand,
+        goal: Goal::MostPoints,
+    };
+    // the Skat belongs to the declarer, trick or not
+    assert_eq!(Outcome::Points(2 + 11 + 14), solve(&position));
+    let position = OpenPosition {
+        declarer: 2,
+        ..position
+    };
+    assert_eq!(Outcome::Points(14), solve(&position));
+
+    // in Ramsch the last trick takes it
+    let position = OpenPosition {
+        goal: Goal::FewestPoints,
+        ..position
+    };
+    assert_eq!(Outcome::Points(0), solve(&position));
+    let position = OpenPosition {
+        declarer: 0,
+        ..position
+    };
+    assert_eq!(Outcome::Points(2 + 11 + 14), solve(&position));
+}
+
+#[test]
+fn test_split_jacks() {
+    // the Jack of Spades sits between the Jacks of Clubs and Diamonds, so they don't play the
+    // same way
+    let position = OpenPosition {
+        hands: [
+            vec![
+                card(Hearts, Seven),
+                card(Spades, Eight),
+                card(Spades, Seven),
+                card(Hearts, Eight),
+            ],
+            vec![
+                card(Clubs, Eight),
+                card(Hearts, Ace),
+                card(Clubs, Jack),
+                card(Diamonds, Jack),
+            ],
+            vec![
+                card(Clubs, King),
+                card(Hearts, Queen),
+                card(Spades, Jack),
+                card(Diamonds, Ace),
+            ],
+        ],
+        skat: vec![card(Hearts, Jack), card(Clubs, Seven)],
+        trick: Vec::new(),
+        leader: 1,
+        declarer: 2,
+        game_type: GameType::Grand,
+        goal: Goal::FewestPoints,
+    };
+    assert_eq!(8, brute_force(&position));
+    assert_eq!(Outcome::Points(8), solve(&position));
+}
+
+#[test]
+fn test_matches_brute_force() {
+    let game_types = [
+        (GameType::Suit(Hearts), Goal::MostPoints),
+        (GameType::Grand, Goal::MostPoints),
+        (GameType::Null, Goal::NoTrick),
+        (GameType::Grand, Goal::FewestPoints),
+    ];
+    let mut rng = StdRng::seed_from_u64(24);
+    for round in 0..40 {
+        let (game_type, goal) = game_types[round % game_types.len()];
+        let mut deck = full_deck();
+        deck.shuffle(&mut rng);
+        let hands = [
+            deck[0..4].to_vec(),
+            deck[4..8].to_vec(),
+            deck[8..12].to_vec(),
+        ];
+        let position = OpenPosition {
+            hands,
+            skat: deck[12..14].to_vec(),
+            trick: Vec::new(),
+            leader: round % 3,
+            declarer: round / 3 % 3,
+            game_type,
+            goal,
+        };
+        let expected = brute_force(&position);
+        let outcome = match goal {
+            Goal::NoTrick => Outcome::Null { won: expected == 0 },
+            _ => Outcome::Points(expected),
+        };
+        assert_eq!(outcome, solve(&position), "{position:?}");
+
+        let best = evaluate_cards(&position)
+            .into_iter()
+            .map(|(_, value)| value);
+        let leader_maximizes = (position.leader == position.declarer) == (goal == Goal::MostPoints);
+        let best = if leader_maximizes {
+            best.max()
+        } else {
+            best.min()
+        };
+        assert_eq!(Some(expected), best, "{position:?}");
+    }
+}